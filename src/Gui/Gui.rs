@@ -6,6 +6,7 @@ use ratatui::widgets::{
 use ratatui::Frame;
 
 use crate::GuiState;
+use UTermux::utils::scheduler::Countdown;
 
 // 渲染通用布局和个人信息
 pub fn render_common_layout(frame: &mut Frame, state: &GuiState) -> (Rect, Rect) {
@@ -29,7 +30,25 @@ pub fn render_common_layout(frame: &mut Frame, state: &GuiState) -> (Rect, Rect)
                 .borders(Borders::ALL)
                 .border_style(Style::new().red()),
         );
-    frame.render_widget(personal_info, layout[0]);
+    // 有定时任务时在标题栏右侧显示最近一个任务的倒计时
+    let countdowns = state.countdown.borrow().clone();
+    match countdowns
+        .iter()
+        .min_by_key(|(_, countdown)| countdown.fire_at)
+    {
+        Some((id, countdown)) => {
+            let [info_area, countdown_area] = *Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+                .split(layout[0])
+            else {
+                return (layout[1], layout[2]);
+            };
+            frame.render_widget(personal_info, info_area);
+            render_countdown(frame, *id, countdown, countdowns.len(), countdown_area);
+        }
+        None => frame.render_widget(personal_info, layout[0]),
+    }
 
     // 渲染控制台
     render_console(frame, state, layout[2]);
@@ -37,12 +56,39 @@ pub fn render_common_layout(frame: &mut Frame, state: &GuiState) -> (Rect, Rect)
     (layout[1], layout[2]) // 返回中间内容区域和底部控制台区域的 Rect
 }
 
+// 渲染倒计时状态
+fn render_countdown(frame: &mut Frame, id: u64, countdown: &Countdown, total: usize, area: Rect) {
+    let remaining = countdown.remaining();
+    let secs = remaining.as_secs();
+    let text = format!(
+        "#{} {:02}:{:02}:{:02}.{} 后开抢  目标 {}  提前 {}ms",
+        id,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60,
+        remaining.subsec_millis() / 100,
+        countdown.target.format("%m-%d %H:%M:%S"),
+        countdown.lead.as_millis()
+    );
+
+    let widget = Paragraph::new(text)
+        .style(Style::new().green().bold())
+        .block(
+            Block::new()
+                .title(format!("倒计时（共{}个任务）", total))
+                .title_style(Style::new().white().bold())
+                .borders(Borders::ALL)
+                .border_style(Style::new().red()),
+        );
+    frame.render_widget(widget, area);
+}
+
 // 渲染主界面
 pub fn render_gui(frame: &mut Frame, state: &mut GuiState) {
     let content_area = render_common_layout(frame, state);
 
     // 功能列表
-    let items = vec![
+    let items = [
         "1.登录🔑️",
         "2.定时🕒️",
         "3.开始抢票🎫️",
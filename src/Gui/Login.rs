@@ -81,7 +81,13 @@ pub async fn handle_login_input(state: &mut GuiState) {
                     state.user_agent = state.input_buffer.clone();
                 }
                 // 无论在哪个输入框，都发送请求
-                match query_mobile_phone(&state.auth_token, &state.user_agent).await {
+                match query_mobile_phone(
+                    &state.request_context,
+                    &state.auth_token,
+                    &state.user_agent,
+                )
+                .await
+                {
                     Ok(response) => state.add_console_message(response),
                     Err(e) => state.add_console_message(format!("错误: {}", e)),
                 }
@@ -1,4 +1,4 @@
-use crate::api::queryMobilePhone::get_config_path;
+use crate::api::strategy::{ReceiveMode, ReceiveStrategy};
 use crate::Gui::Gui::render_common_layout;
 use crate::{function_list, GuiState};
 use crossterm::event::KeyCode;
@@ -9,11 +9,10 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, List};
 use ratatui::Frame;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
-use std::path::PathBuf;
 use tokio;
-use crate::api::queryMobilePhone::TokenStorage;
+use UTermux::engine::accounts::{self, TokenRecord};
+use UTermux::engine::events::GrabEvent;
+use UTermux::engine::games::{self, DEFAULT_GAMES};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SettingState {
@@ -25,55 +24,121 @@ pub struct SettingState {
     pub games: Vec<String>,
     // 添加账号管理相关字段
     #[serde(skip)]
-    pub accounts: Vec<String>, // 用于存储账号列表
+    pub accounts: Vec<TokenRecord>, // 已保存的账号
+    #[serde(skip)]
+    pub account_input: Option<(AccountInput, String)>, // 正在输入的账号备注或新token
+    #[serde(skip)]
+    pub confirm_delete: bool, // 再按一次删除键确认删除
+    #[serde(skip)]
+    pub strategy: ReceiveStrategy, // 抢票策略
+    #[serde(skip)]
+    pub load_errors: Vec<String>, // 启动时读取配置失败的原因，显示在控制台中
+}
+
+// 账号管理弹窗中需要输入的内容
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountInput {
+    Alias, // 备注
+    Token, // 新的authToken
+}
+
+// 抢票策略弹窗中可调整的字段
+const STRATEGY_FIELDS: [&str; 10] = [
+    "发送模式",
+    "并发任务数",
+    "爆发请求数",
+    "抢票窗口",
+    "单次超时",
+    "请求间隔",
+    "启动间隔",
+    "提前发出",
+    "预热时间",
+    "预热连接数",
+];
+
+// 策略字段当前值的显示文本
+fn strategy_value(strategy: &ReceiveStrategy, index: usize) -> String {
+    match index {
+        0 => match strategy.mode {
+            ReceiveMode::Steady => "稳定".to_string(),
+            ReceiveMode::Burst => "爆发".to_string(),
+        },
+        1 => strategy.workers.to_string(),
+        2 => strategy.burst_count.to_string(),
+        3 => format!("{} ms", strategy.window_ms),
+        4 => format!("{} ms", strategy.request_timeout_ms),
+        5 => format!("{} ms", strategy.interval_ms),
+        6 => format!("{} ms", strategy.stagger_ms),
+        7 => format!("{} ms", strategy.lead_ms),
+        8 => format!("{} ms", strategy.warmup_ms),
+        9 => strategy.warm_connections.to_string(),
+        _ => String::new(),
+    }
+}
+
+// 按步长调整策略字段
+fn adjust_strategy(strategy: &mut ReceiveStrategy, index: usize, increase: bool) {
+    fn step<T>(value: T, delta: T, min: T, max: T, increase: bool) -> T
+    where
+        T: Copy + Ord + std::ops::Add<Output = T> + std::ops::Sub<Output = T>,
+    {
+        if increase {
+            (value + delta).min(max)
+        } else if value >= min + delta {
+            value - delta
+        } else {
+            min
+        }
+    }
+
+    match index {
+        0 => {
+            strategy.mode = match strategy.mode {
+                ReceiveMode::Steady => ReceiveMode::Burst,
+                ReceiveMode::Burst => ReceiveMode::Steady,
+            }
+        }
+        1 => strategy.workers = step(strategy.workers, 1, 1, 16, increase),
+        2 => strategy.burst_count = step(strategy.burst_count, 1, 1, 32, increase),
+        3 => strategy.window_ms = step(strategy.window_ms, 500, 500, 60_000, increase),
+        4 => {
+            strategy.request_timeout_ms =
+                step(strategy.request_timeout_ms, 100, 100, 10_000, increase)
+        }
+        5 => strategy.interval_ms = step(strategy.interval_ms, 10, 0, 2000, increase),
+        6 => strategy.stagger_ms = step(strategy.stagger_ms, 10, 0, 1000, increase),
+        7 => strategy.lead_ms = step(strategy.lead_ms, 10, 0, 1000, increase),
+        8 => strategy.warmup_ms = step(strategy.warmup_ms, 500, 0, 30_000, increase),
+        9 => {
+            strategy.warm_connections = step(strategy.warm_connections, 1, 0, 16, increase)
+        }
+        _ => {}
+    }
 }
 
 impl Default for SettingState {
     fn default() -> Self {
-        let config_path = get_config_path();
-        // 读取并解析账号配置
-        let accounts = if let Some(path) = config_path {
-            if let Ok(content) = fs::read_to_string(path) {
-                if let Ok(storage) = serde_json::from_str::<TokenStorage>(&content) {
-                    storage.records.into_iter()
-                        .map(|record| {
-                            if record.active {
-                                format!("[*] {}", record.mobile_phone)
-                            } else {
-                                format!("[ ] {}", record.mobile_phone)
-                            }
-                        })
-                        .collect()
-                } else {
-                    vec![]
-                }
-            } else {
-                vec![]
-            }
-        } else {
+        // 读取失败时显示为空并记录原因，打开对应的设置时重新读取，仍然失败时不能修改
+        let mut load_errors = vec![];
+        let accounts = accounts::list().unwrap_or_else(|e| {
+            load_errors.push(format!("读取账号失败: {}", e));
             vec![]
-        };
+        });
 
         // 从配置文件加载游戏列表
-        let (games, game_selections) = if let Some(path) = Self::get_config_path().parent() {
-            let config_path = path.join("AppGame.json");
-            if let Ok(content) = fs::read_to_string(config_path) {
-                if let Ok(game_map) = serde_json::from_str::<HashMap<String, GameConfig>>(&content) {
-                    let mut games: Vec<String> = game_map.keys().cloned().collect();
-                    games.sort(); // 对游戏列表进行排序
-                    let selections: Vec<bool> = games.iter()
-                        .map(|game| game_map.get(game).map(|config| config.active).unwrap_or(false))
-                        .collect();
-                    (games, selections)
-                } else {
-                    (vec![], vec![])
-                }
-            } else {
-                (vec![], vec![])
-            }
-        } else {
-            (vec![], vec![])
-        };
+        let game_map = games::load().unwrap_or_else(|e| {
+            load_errors.push(format!("读取游戏设置失败: {}", e));
+            Default::default()
+        });
+        let mut games: Vec<String> = game_map.keys().cloned().collect();
+        games.sort(); // 对游戏列表进行排序
+        let game_selections: Vec<bool> = games.iter()
+            .map(|game| game_map.get(game).map(|config| config.active).unwrap_or(false))
+            .collect();
+        let strategy = ReceiveStrategy::load().unwrap_or_else(|e| {
+            load_errors.push(format!("读取抢票策略失败: {}", e));
+            Default::default()
+        });
 
         Self {
             setting_index: 0,
@@ -82,103 +147,104 @@ impl Default for SettingState {
             popup_index: 0,
             games,
             accounts,
+            account_input: None,
+            confirm_delete: false,
+            strategy,
+            load_errors,
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct GameConfig {
-    active: bool,
-    communityId: String,
-    #[serde(flatten)]
-    amounts: std::collections::HashMap<String, u32>,
-    red_pack_tasks: HashMap<String, Vec<String>>,
+// 账号token状态的标记
+fn status_badge(record: &TokenRecord) -> Span<'static> {
+    let color = match record.valid {
+        Some(true) => Color::Green,
+        Some(false) => Color::Red,
+        None => Color::DarkGray,
+    };
+    let checked_at = record
+        .last_validated_at
+        .map(|time| format!(" {}", time.format("%m-%d %H:%M")))
+        .unwrap_or_default();
+    Span::styled(
+        format!("  [{}{}]", record.status(), checked_at),
+        Style::default().fg(color),
+    )
 }
 
 impl SettingState {
-    pub fn new(gui_state: &mut GuiState) -> Self {
+    pub async fn new(gui_state: &mut GuiState) -> Self {
         let mut state = Self::default();
         
         // 如果游戏列表为空，添加默认游戏
         if state.games.is_empty() {
-            state.games = vec![
-                "三国杀".to_string(),
-                "王者荣耀".to_string(),
-                "火影忍者".to_string(),
-            ];
+            state.games = DEFAULT_GAMES.iter().map(|game| game.to_string()).collect();
             state.game_selections = vec![false; state.games.len()];
             gui_state.add_console_message(format!("使用默认游戏列表：{:?}", state.games));
         } else {
             gui_state.add_console_message(format!("从配置文件加载游戏列表：{:?}", state.games));
         }
 
-        // 确保配置文件存在，如果不存在则创建默认配置
-        if !Self::get_config_path().exists() {
-            Self::save_settings_static(&state.games, &state.game_selections, gui_state);
+        // 确保配置文件中有游戏配置，如果没有则创建默认配置
+        if games::load().is_ok_and(|game_map| game_map.is_empty()) {
+            let selections: Vec<(String, bool)> =
+                state.games.iter().map(|game| (game.clone(), false)).collect();
+            if let Err(e) = games::update_games(
+                &gui_state.request_context,
+                &selections,
+                &gui_state.console_sender,
+            )
+            .await
+            {
+                gui_state.add_console_message(format!("保存设置失败: {}", e));
+            }
         }
 
         state
     }
 
-    pub fn get_config_path() -> PathBuf {
-        let mut path = dirs::config_dir().expect("无法获取配置目录");
-        path.push("UTermux");
-        path.push("AppGame.json");
-        path
-    }
-
     pub fn save_settings(&self, gui_state: &mut GuiState) {
-        let config_path = Self::get_config_path();
-        if let Some(parent) = config_path.parent() {
-            if let Err(e) = fs::create_dir_all(parent) {
-                gui_state.add_console_message(format!("创建配置目录失败: {}", e));
-                return;
+        // 更新游戏的选择状态，保留已获取的优惠券
+        let result = games::update(|game_map| {
+            for (game, selected) in self.games.iter().zip(&self.game_selections) {
+                if let Some(config) = game_map.get_mut(game) {
+                    config.active = *selected;
+                }
             }
-        }
+            Ok(())
+        });
 
-        // 创建游戏名称到选择状态的映射
-        let mut game_map = HashMap::new();
-        for (i, game) in self.games.iter().enumerate() {
-            game_map.insert(game, self.game_selections[i]);
+        if let Err(e) = result {
+            gui_state.add_console_message(format!("保存设置失败: {}", e));
+        } else {
+            gui_state.add_console_message("设置保存成功".to_string());
         }
+    }
 
-        match serde_json::to_string_pretty(&game_map) {
-            Ok(json) => {
-                if let Err(e) = fs::write(&config_path, json) {
-                    gui_state.add_console_message(format!("保存设置失败: {}", e));
-                } else {
-                    gui_state.add_console_message("设置保存成功".to_string());
+    // 读取失败时返回 false
+    fn load_settings(&mut self, gui_state: &mut GuiState) -> bool {
+        match games::load() {
+            Ok(game_map) => {
+                // 启动时读取失败的游戏列表在修复配置文件后重新读取
+                if self.games.is_empty() {
+                    self.games = game_map.keys().cloned().collect();
+                    self.games.sort();
                 }
-            }
-            Err(e) => gui_state.add_console_message(format!("序列化设置失败: {}", e)),
-        }
-    }
+                // 重置所有选择状态
+                self.game_selections = vec![false; self.games.len()];
 
-    fn load_settings(&mut self, gui_state: &mut GuiState) {
-        match fs::read_to_string(Self::get_config_path()) {
-            Ok(content) => {
-                match serde_json::from_str::<HashMap<String, GameConfig>>(&content) {
-                    Ok(game_map) => {
-                        // 重置所有选择状态
-                        self.game_selections.fill(false);
-
-                        // 根据保存的配置更新选择状态
-                        for (i, game) in self.games.iter().enumerate() {
-                            if let Some(config) = game_map.get(game) {
-                                self.game_selections[i] = config.active;
-                            }
-                        }
-                        gui_state.add_console_message("设置加载成功".to_string());
-                    }
-                    Err(e) => {
-                        gui_state.add_console_message(format!("解析设置文件失败: {}", e));
-                        self.game_selections = vec![false; self.games.len()];
+                // 根据保存的配置更新选择状态
+                for (i, game) in self.games.iter().enumerate() {
+                    if let Some(config) = game_map.get(game) {
+                        self.game_selections[i] = config.active;
                     }
                 }
+                gui_state.add_console_message("设置加载成功".to_string());
+                true
             }
             Err(e) => {
-                gui_state.add_console_message(format!("读取设置文件失败: {}", e));
-                self.game_selections = vec![false; self.games.len()];
+                gui_state.add_console_message(format!("读取设置失败，请先修复配置文件: {}", e));
+                false
             }
         }
     }
@@ -186,7 +252,12 @@ impl SettingState {
     // 渲染设置页面
     pub fn render(&self, frame: &mut Frame, state: &GuiState) {
         let (content_area, _) = render_common_layout(frame, state);
-        let items = vec!["1.选择游戏🎮", "2.账号管理📒", "3.关于作者🧑"]
+        let items = [
+            "1.选择游戏🎮",
+            "2.账号管理📒",
+            "3.抢票策略⚙️",
+            "4.关于作者🧑",
+        ]
             .iter()
             .enumerate()
             .map(|(i, item)| {
@@ -213,7 +284,8 @@ impl SettingState {
             let title = match self.setting_index {
                 0 => "选择游戏(可多选)",
                 1 => "账号管理(可多选)",
-                2 => "关于作者",
+                2 => "抢票策略(←/→调整)",
+                3 => "关于作者",
                 _ => "",
             };
             self.render_popup(frame, content_area, title);
@@ -269,23 +341,34 @@ impl SettingState {
             }
             1 => {
                 // 账号管理列表渲染逻辑
+                let header = match &self.account_input {
+                    Some((AccountInput::Alias, buffer)) => {
+                        Line::from(format!("备注: {}_  (Enter 保存 Esc 取消)", buffer))
+                    }
+                    Some((AccountInput::Token, buffer)) => {
+                        // token只显示为星号
+                        let masked = "*".repeat(buffer.chars().count());
+                        Line::from(format!("新token: {}_  (Enter 校验并替换 Esc 取消)", masked))
+                    }
+                    None => Line::from(
+                        "Enter 激活  O 仅激活  K/J 上下移  N 备注  T 换token  D 删除  V 校验",
+                    )
+                    .fg(Color::DarkGray),
+                };
+                frame.render_widget(header, inner_layout[0]);
                 let items: Vec<Line> = self
                     .accounts
                     .iter()
                     .enumerate()
                     .map(|(i, account)| {
-                        let is_active = account.starts_with("[*]");
-                        let account_text = account.trim_start_matches("[*] ")
-                            .trim_start_matches("[ ] ");
-                        
-                        let prefix = if is_active {
+                        let prefix = if account.active {
                             Span::styled("[*] ", Style::default().fg(Color::Red))
                         } else {
                             Span::raw("[ ] ")
                         };
-                        
-                        let account_span = Span::raw(account_text);
-                        let spans = vec![prefix, account_span];
+
+                        let account_span = Span::raw(account.label());
+                        let spans = vec![prefix, account_span, status_badge(account)];
 
                         if i == self.popup_index {
                             Line::from(spans)
@@ -302,11 +385,45 @@ impl SettingState {
 
                 frame.render_widget(list, inner_layout[1]);
             }
+            2 => {
+                // 抢票策略渲染逻辑
+                let items: Vec<Line> = STRATEGY_FIELDS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let spans = vec![
+                            Span::raw(format!("{}: ", field)),
+                            Span::styled(
+                                strategy_value(&self.strategy, i),
+                                Style::default().fg(Color::Red),
+                            ),
+                        ];
+
+                        if i == self.popup_index {
+                            Line::from(spans)
+                                .style(Style::default().fg(Color::Black).bg(Color::White))
+                        } else {
+                            Line::from(spans)
+                        }
+                    })
+                    .collect();
+
+                let list = List::new(items).block(Block::default().borders(Borders::NONE));
+
+                frame.render_widget(list, inner_layout[1]);
+            }
             _ => {}
         }
     }
 
     pub fn setting_handle_key(gui_state: &mut GuiState, key: KeyCode) {
+        if gui_state.setting_state.show_prop
+            && gui_state.setting_state.setting_index == 1
+            && Self::account_handle_key(gui_state, key)
+        {
+            return;
+        }
+
         match key {
             KeyCode::Esc => {
                 if gui_state.setting_state.show_prop {
@@ -334,6 +451,10 @@ impl SettingState {
                                     gui_state.setting_state.popup_index.saturating_sub(1);
                             }
                         }
+                        2 => {
+                            gui_state.setting_state.popup_index =
+                                gui_state.setting_state.popup_index.saturating_sub(1);
+                        }
                         _ => {}
                     }
                     return;
@@ -365,130 +486,277 @@ impl SettingState {
                                     gui_state.setting_state.popup_index.saturating_add(1);
                             }
                         }
+                        2 => {
+                            if gui_state.setting_state.popup_index < STRATEGY_FIELDS.len() - 1 {
+                                gui_state.setting_state.popup_index += 1;
+                            }
+                        }
                         _ => {}
                     }
                     return;
                 }
 
-                if gui_state.setting_state.setting_index < 2 {
+                if gui_state.setting_state.setting_index < 3 {
                     gui_state.setting_state.setting_index =
                         gui_state.setting_state.setting_index.saturating_add(1);
                 }
             }
+            KeyCode::Left | KeyCode::Right => {
+                if gui_state.setting_state.show_prop && gui_state.setting_state.setting_index == 2
+                {
+                    Self::update_strategy(gui_state, key == KeyCode::Right);
+                }
+            }
             KeyCode::Enter => {
                 if gui_state.setting_state.show_prop {
                     match gui_state.setting_state.setting_index {
                         0 => {
                             if !gui_state.setting_state.games.is_empty() {
-                                // 切换游戏选中状态
-                                gui_state.setting_state.game_selections
-                                    [gui_state.setting_state.popup_index] = !gui_state
-                                    .setting_state
-                                    .game_selections[gui_state.setting_state.popup_index];
-
-                                // 保存设置
-                                let games = gui_state.setting_state.games.clone();
-                                let selections = gui_state.setting_state.game_selections.clone();
-                                let console_sender = gui_state.console_sender.clone();
-
-                                tokio::spawn(async move {
-                                    let mut gui_state_clone = GuiState {
-                                        console_sender,
-                                        current_page: crate::function_list::Main,
-                                        selected_index: 0,
-                                        input_buffer: String::new(),
-                                        auth_token: String::new(),
-                                        console_info: String::new(),
-                                        console_receiver: tokio::sync::mpsc::channel::<String>(100)
-                                            .1,
-                                        console_scroll: 0,
-                                        max_console_lines: 100,
-                                        auto_scroll: true,
-                                        setting_state: SettingState::default(),
-                                        setting_index: 0,
-                                        show_prop: false,
-                                        active_input: 0,
-                                        last_key: KeyCode::Enter,
-                                        user_agent: String::new(),
-                                    };
-
-                                    SettingState::save_settings_static(
-                                        &games,
-                                        &selections,
-                                        &mut gui_state_clone,
-                                    )
-                                    .await;
-                                });
-
-                                gui_state.add_console_message(
-                                    format!(
-                                        "已保存-{}",
-                                        gui_state.setting_state.games
-                                            [gui_state.setting_state.popup_index]
-                                    )
-                                    .to_string(),
-                                );
+                                // 切换游戏选中状态，只保存切换的游戏，按操作顺序在锁内写入
+                                let index = gui_state.setting_state.popup_index;
+                                let game = gui_state.setting_state.games[index].clone();
+                                let active = !gui_state.setting_state.game_selections[index];
+                                match games::set_active(&[(game.clone(), active)]) {
+                                    Ok(_) => {
+                                        gui_state.setting_state.game_selections[index] = active;
+                                        gui_state.add_console_message(format!("已保存-{}", game));
+                                    }
+                                    Err(e) => {
+                                        gui_state.add_console_message(format!("保存设置失败: {}", e));
+                                    }
+                                }
+
+                                // 启用的游戏在后台获取最新的优惠券，不修改启用状态
+                                if gui_state.setting_state.game_selections[index] && active {
+                                    let events = gui_state.console_sender.clone();
+                                    let request_context = gui_state.request_context.clone();
+                                    tokio::spawn(async move {
+                                        if let Err(e) =
+                                            games::fetch_coupons(&request_context, &[game], &events).await
+                                        {
+                                            events.message(format!("获取红包任务ID失败: {}", e)).await;
+                                        }
+                                    });
+                                }
                             }
                         }
                         1 => {
                             if !gui_state.setting_state.accounts.is_empty() {
                                 let account_index = gui_state.setting_state.popup_index;
                                 
-                                // 读取当前的token存储
-                                if let Some(path) = get_config_path() {
-                                    if let Ok(content) = fs::read_to_string(&path) {
-                                        if let Ok(mut storage) = serde_json::from_str::<TokenStorage>(&content) {
-                                            // 切换选中状态
-                                            if let Some(record) = storage.records.get_mut(account_index) {
-                                                record.active = !record.active;
-                                            }
-                                            
-                                            // 保存更新后的配置
-                                            if let Ok(json_content) = serde_json::to_string_pretty(&storage) {
-                                                if let Err(e) = fs::write(&path, json_content) {
-                                                    gui_state.add_console_message(
-                                                        format!("保存账号配置失败: {}", e)
-                                                    );
-                                                } else {
-                                                    // 更新UI显示
-                                                    gui_state.setting_state.accounts = storage.records
-                                                        .into_iter()
-                                                        .map(|record| {
-                                                            if record.active {
-                                                                format!("[*] {}", record.mobile_phone)
-                                                            } else {
-                                                                format!("[ ] {}", record.mobile_phone)
-                                                            }
-                                                        })
-                                                        .collect();
-                                                    
-                                                    gui_state.add_console_message(
-                                                        "账号状态切换成功".to_string()
-                                                    );
-                                                }
-                                            }
-                                        }
+                                match accounts::toggle(account_index) {
+                                    Ok(records) => {
+                                        // 更新UI显示
+                                        gui_state.setting_state.accounts = records;
+                                        gui_state.add_console_message(
+                                            "账号状态切换成功".to_string()
+                                        );
+                                    }
+                                    Err(e) => {
+                                        gui_state.add_console_message(
+                                            format!("保存账号配置失败: {}", e)
+                                        );
                                     }
                                 }
                             }
                         }
+                        2 => {
+                            // 切换发送模式
+                            if gui_state.setting_state.popup_index == 0 {
+                                Self::update_strategy(gui_state, true);
+                            }
+                        }
                         _ => {}
                     }
                     return;
                 }
-                gui_state.setting_state.show_prop = true;
-                gui_state.setting_state.popup_index = 0;
-                if gui_state.setting_state.setting_index == 0 {
+                // 打开设置前重新读取，读取失败时不打开，以免修改时覆盖配置文件
+                let loaded = if gui_state.setting_state.setting_index == 0 {
                     // 加载游戏设置
                     let mut setting_state = gui_state.setting_state.clone();
-                    setting_state.load_settings(gui_state);
+                    let loaded = setting_state.load_settings(gui_state);
                     gui_state.setting_state = setting_state;
+                    loaded
+                } else if gui_state.setting_state.setting_index == 1 {
+                    // 重新读取账号和校验结果
+                    match accounts::list() {
+                        Ok(accounts) => {
+                            gui_state.setting_state.accounts = accounts;
+                            true
+                        }
+                        Err(e) => {
+                            gui_state.add_console_message(format!("读取账号失败，请先修复配置文件: {}", e));
+                            false
+                        }
+                    }
+                } else if gui_state.setting_state.setting_index == 2 {
+                    // 重新读取抢票策略
+                    match ReceiveStrategy::load() {
+                        Ok(strategy) => {
+                            gui_state.setting_state.strategy = strategy;
+                            true
+                        }
+                        Err(e) => {
+                            gui_state.add_console_message(format!("读取抢票策略失败，请先修复配置文件: {}", e));
+                            false
+                        }
+                    }
+                } else {
+                    true
+                };
+                if loaded {
+                    gui_state.setting_state.show_prop = true;
+                    gui_state.setting_state.popup_index = 0;
                 }
             }
             _ => {}
         }
     }
 
+    // 账号管理弹窗中的操作，返回 true 表示按键已处理
+    fn account_handle_key(gui_state: &mut GuiState, key: KeyCode) -> bool {
+        // 正在输入备注或新token
+        if let Some((input, mut buffer)) = gui_state.setting_state.account_input.take() {
+            match key {
+                KeyCode::Char(c) => buffer.push(c),
+                KeyCode::Backspace => {
+                    buffer.pop();
+                }
+                KeyCode::Enter => {
+                    Self::submit_account_input(gui_state, input, buffer);
+                    return true;
+                }
+                KeyCode::Esc => return true,
+                _ => {}
+            }
+            gui_state.setting_state.account_input = Some((input, buffer));
+            return true;
+        }
+
+        let confirm_delete = std::mem::take(&mut gui_state.setting_state.confirm_delete);
+        let index = gui_state.setting_state.popup_index;
+        let Some(record) = gui_state.setting_state.accounts.get(index).cloned() else {
+            return false;
+        };
+        let phone = record.mobile_phone.as_str();
+
+        let result = match key {
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                if !confirm_delete {
+                    gui_state.setting_state.confirm_delete = true;
+                    gui_state
+                        .add_console_message(format!("再按一次 D 删除账号 {}", record.label()));
+                    return true;
+                }
+                accounts::remove(phone)
+                    .map(|records| (records, format!("已删除账号 {}", record.label())))
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => accounts::activate(phone, true)
+                .map(|records| (records, format!("只激活账号 {}", record.label()))),
+            KeyCode::Char('k') | KeyCode::Char('K') => {
+                accounts::move_by(phone, -1).map(|records| (records, String::new()))
+            }
+            KeyCode::Char('j') | KeyCode::Char('J') => {
+                accounts::move_by(phone, 1).map(|records| (records, String::new()))
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                let alias = record.alias.clone().unwrap_or_default();
+                gui_state.setting_state.account_input = Some((AccountInput::Alias, alias));
+                return true;
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                gui_state.setting_state.account_input = Some((AccountInput::Token, String::new()));
+                return true;
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                // 立即校验所有账号
+                let events = gui_state.console_sender.clone();
+                let request_context = gui_state.request_context.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = accounts::validate(&request_context, &events).await {
+                        events.message(format!("保存账号校验结果失败: {}", e)).await;
+                    }
+                });
+                gui_state.add_console_message("正在校验账号token...".to_string());
+                return true;
+            }
+            _ => return false,
+        };
+
+        match result {
+            Ok((records, message)) => {
+                // 选中项跟随移动的账号
+                gui_state.setting_state.popup_index = records
+                    .iter()
+                    .position(|r| r.mobile_phone == record.mobile_phone)
+                    .unwrap_or(index)
+                    .min(records.len().saturating_sub(1));
+                gui_state.setting_state.accounts = records;
+                if !message.is_empty() {
+                    gui_state.add_console_message(message);
+                }
+            }
+            Err(e) => gui_state.add_console_message(format!("保存账号配置失败: {}", e)),
+        }
+        true
+    }
+
+    // 保存输入的备注，或校验并替换新token
+    fn submit_account_input(gui_state: &mut GuiState, input: AccountInput, buffer: String) {
+        let index = gui_state.setting_state.popup_index;
+        let Some(record) = gui_state.setting_state.accounts.get(index).cloned() else {
+            return;
+        };
+        match input {
+            AccountInput::Alias => match accounts::rename(&record.mobile_phone, Some(&buffer)) {
+                Ok(records) => {
+                    gui_state.setting_state.accounts = records;
+                    gui_state
+                        .add_console_message(format!("已修改账号 {} 的备注", record.mobile_phone));
+                }
+                Err(e) => gui_state.add_console_message(format!("保存账号配置失败: {}", e)),
+            },
+            AccountInput::Token => {
+                let events = gui_state.console_sender.clone();
+                let request_context = gui_state.request_context.clone();
+                let mobile_phone = record.mobile_phone;
+                tokio::spawn(async move {
+                    match accounts::replace_token(&request_context, &mobile_phone, &buffer, "")
+                        .await
+                    {
+                        Ok(_) => {
+                            events
+                                .send(GrabEvent::AccountChecked {
+                                    account: mobile_phone.clone(),
+                                    valid: true,
+                                })
+                                .await;
+                            events
+                                .message(format!("已更新账号 {} 的token", mobile_phone))
+                                .await;
+                        }
+                        Err(e) => events.message(format!("更新token失败: {}", e)).await,
+                    }
+                });
+                gui_state.add_console_message("正在校验新的token...".to_string());
+            }
+        }
+    }
+
+    // 调整当前选中的策略字段并保存
+    fn update_strategy(gui_state: &mut GuiState, increase: bool) {
+        let index = gui_state.setting_state.popup_index;
+        // 在最新保存的策略上调整，不覆盖其他进程的修改
+        let result = ReceiveStrategy::update(|strategy| {
+            adjust_strategy(strategy, index, increase);
+            Ok(strategy.clone())
+        });
+        match result {
+            Ok(strategy) => gui_state.setting_state.strategy = strategy,
+            Err(e) => gui_state.add_console_message(format!("保存抢票策略失败: {}", e)),
+        }
+    }
+
     // 添加一个辅助函数来计算弹窗的位置和大小
     pub fn centered_rect(&self, percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         let popup_layout = Layout::default()
@@ -510,115 +778,6 @@ impl SettingState {
             .split(popup_layout[1])[1]
     }
 
-    pub async fn save_settings_static(
-        games: &[String],
-        selections: &[bool],
-        gui_state: &mut GuiState,
-    ) {
-        let config_path = Self::get_config_path();
-        gui_state.add_console_message(format!("配置文件路径: {:?}", config_path));
-
-        // 确保配置目录存在
-        if let Some(parent) = config_path.parent() {
-            if let Err(e) = fs::create_dir_all(parent) {
-                gui_state.add_console_message(format!("创建配置目录失败: {}", e));
-                return;
-            }
-            gui_state.add_console_message("配置目录创建成功".to_string());
-        }
-
-        // 如果文件不存在，创建一个空的配置文件
-        if !config_path.exists() {
-            let default_config: HashMap<String, GameConfig> = HashMap::new();
-            let json = serde_json::to_string_pretty(&default_config).unwrap_or_default();
-            match fs::write(&config_path, json) {
-                Ok(_) => gui_state.add_console_message("配置文件创建成功".to_string()),
-                Err(e) => {
-                    gui_state.add_console_message(format!("创建配置文件失败: {}", e));
-                    return;
-                }
-            }
-        }
-
-        // 读取现有配置
-        let mut game_map = match fs::read_to_string(&config_path) {
-            Ok(content) => {
-                serde_json::from_str::<std::collections::HashMap<String, GameConfig>>(&content)
-                    .unwrap_or_default()
-            }
-            Err(_) => std::collections::HashMap::new(),
-        };
-
-        for (i, game) in games.iter().enumerate() {
-            let (community_id, mut red_pack_tasks) = match game.as_str() {
-                "三国杀" => ("14", HashMap::new()),
-                "王者荣耀" => ("7", HashMap::new()),
-                "火影忍者" => ("10", HashMap::new()),
-                _ => ("14", HashMap::new()),
-            };
-
-            if selections[i] {
-                if let Some(auth_token) = crate::api::queryMobilePhone::read_saved_token() {
-                    match crate::api::info::get_info(auth_token, community_id.to_string()).await {
-                        Ok(info) => {
-                            // 解析返回的信息
-                            for zone_info in info.split(';') {
-                                if let Some((zone_name, ids)) = zone_info.split_once(':') {
-                                    red_pack_tasks.insert(
-                                        zone_name.to_string(),
-                                        ids.split(',').map(String::from).collect(),
-                                    );
-                                }
-                            }
-                            gui_state
-                                .add_console_message(format!("成功获取 {} 的红包任务ID", game));
-                        }
-                        Err(e) => {
-                            gui_state.add_console_message(format!(
-                                "获取 {} 的红包任务ID失败: {}",
-                                game, e
-                            ));
-                        }
-                    }
-                }
-            }
-
-            game_map
-                .entry(game.clone())
-                .and_modify(|config| {
-                    config.active = selections[i];
-                    if !red_pack_tasks.is_empty() {
-                        config.red_pack_tasks = red_pack_tasks.clone();
-                    }
-                })
-                .or_insert(GameConfig {
-                    active: selections[i],
-                    communityId: community_id.to_string(),
-                    amounts: std::collections::HashMap::new(),
-                    red_pack_tasks,
-                });
-        }
-
-        // 保存更新后的配置
-        if let Some(parent) = config_path.parent() {
-            if let Err(e) = fs::create_dir_all(parent) {
-                gui_state.add_console_message(format!("创建配置目录失败: {}", e));
-                return;
-            }
-        }
-
-        match serde_json::to_string_pretty(&game_map) {
-            Ok(json) => {
-                if let Err(e) = fs::write(&config_path, json) {
-                    gui_state.add_console_message(format!("保存设置失败: {}", e));
-                } else {
-                    gui_state.add_console_message("设置保存成功".to_string());
-                }
-            }
-            Err(e) => gui_state.add_console_message(format!("序列化设置失败: {}", e)),
-        }
-    }
-
     // 确保games字段可以被访问
     pub fn get_games(&self) -> &Vec<String> {
         &self.games
@@ -0,0 +1,78 @@
+use color_eyre::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use zeroize::Zeroizing;
+use UTermux::engine::accounts;
+
+// 账号文件加密时，进入主界面前输入口令解锁，返回 false 表示放弃解锁
+pub fn unlock(terminal: &mut DefaultTerminal) -> Result<bool> {
+    // 口令在释放时清零，预留容量避免输入时重新分配留下未清零的副本
+    let mut input = Zeroizing::new(String::with_capacity(64));
+    let mut message = String::new();
+
+    loop {
+        terminal.draw(|frame| unlock_render(frame, &input, &message))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Enter => match accounts::unlock(&input) {
+                    Ok(_) => return Ok(true),
+                    Err(e) => {
+                        message = e.to_string();
+                        input.clear();
+                    }
+                },
+                KeyCode::Esc => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn unlock_render(frame: &mut Frame, input: &str, message: &str) {
+    let [_, area, _] = *Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Length(5),
+            Constraint::Fill(1),
+        ])
+        .split(frame.area())
+    else {
+        return;
+    };
+    let [_, area, _] = *Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Fill(1),
+            Constraint::Percentage(60),
+            Constraint::Fill(1),
+        ])
+        .split(area)
+    else {
+        return;
+    };
+
+    // 口令只显示为星号
+    let lines = vec![
+        Line::from("*".repeat(input.chars().count())),
+        Line::from(message.to_string()).red(),
+        Line::from("Enter 解锁  Esc 退出").dark_gray(),
+    ];
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title("账号文件已加密，请输入口令")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Green)),
+    );
+    frame.render_widget(paragraph, area);
+}
@@ -2,4 +2,5 @@ pub mod Gui;
 pub mod Login;
 pub mod start_grabbing_tickets;
 pub mod timed_ticket_grabbing;
-pub mod Setting;
\ No newline at end of file
+pub mod Setting;
+pub mod Unlock;
\ No newline at end of file
@@ -1,33 +1,12 @@
-use crate::api::info::get_info;
-use crate::api::{queryMobilePhone::read_saved_token, receive::fetch_receive};
 use crate::timed_ticket_grabbing_state;
 use crate::Gui::Gui::render_common_layout;
 use crate::GuiState;
 use crossterm::event::KeyCode;
-use once_cell::sync::Lazy;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, List};
 use ratatui::Frame;
-use serde::Deserialize;
-use serde::Serialize;
-use std::collections::HashMap;
-use std::fs;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::sync::Barrier;
-use std::sync::Mutex;
-
-// 修改数据结构以包含专区名称和对应的任务ID
-static TICKET_IDS: Lazy<Mutex<Vec<(&'static str, Vec<i64>)>>> = Lazy::new(|| {
-    Mutex::new(vec![
-        ("置顶专区", vec![]),
-        ("新人专区", vec![]),
-        ("每周专区", vec![]),
-        ("每日专区", vec![]),
-    ])
-});
-
-static INFO_REQUESTED: AtomicBool = AtomicBool::new(false);
+use UTermux::engine::games::ZONES;
+use UTermux::engine::jobs::{Job, JobList};
 
 #[derive(Debug, Clone)]
 pub struct Button<'a> {
@@ -37,14 +16,15 @@ pub struct Button<'a> {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum State {
+pub enum State {
     Normal,
     Selected,
+    #[allow(dead_code)]
     Active,
 }
 
 #[derive(Debug, Clone, Copy)]
-struct Theme {
+pub struct Theme {
     text: Color,
     background: Color,
     highlight: Color,
@@ -137,56 +117,12 @@ impl Button<'_> {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct GameConfig {
-    active: bool,
-    communityId: String,
-    red_pack_tasks: HashMap<String, Vec<String>>,
-}
-
 // 渲染抢票页面
 pub fn start_ticket_grabbing_render(
     f: &mut Frame,
     gui_state: &mut GuiState,
     timed_state: &mut timed_ticket_grabbing_state,
 ) {
-    // 只在第一次渲染时发送请求
-    if !INFO_REQUESTED.load(Ordering::SeqCst) {
-        INFO_REQUESTED.store(true, Ordering::SeqCst);
-        let auth_token = gui_state.auth_token.clone();
-        tokio::spawn(async move {
-            if let Ok(info) = get_info(auth_token, "14".to_string()).await {
-                // 解析返回的分类和ID信息
-                let categories: Vec<(&str, Vec<i64>)> = info
-                    .split(';')
-                    .filter_map(|cat_info| {
-                        let parts: Vec<&str> = cat_info.split(':').collect();
-                        if parts.len() == 2 {
-                            let ids: Vec<i64> = parts[1]
-                                .split(',')
-                                .filter_map(|id| id.parse().ok())
-                                .collect();
-                            Some((parts[0], ids))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
-
-                let mut tickets = TICKET_IDS.lock().unwrap();
-                // 更新专区对应的任务ID列表
-                for (category, ids) in &categories {
-                    if let Some(idx) = tickets.iter().position(|(t, _)| t == category) {
-                        // 找到匹配的专区，更新其任务ID列表
-                        if !ids.is_empty() {
-                            tickets[idx].1 = ids.clone(); // 更新对应专区的任务ID列表
-                        }
-                    }
-                }
-            }
-        });
-    }
-
     let (content_area, _) = render_common_layout(f, gui_state);
 
     // 将内容区域分为上下两部分，上面是列表，下面是按钮
@@ -208,19 +144,14 @@ pub fn start_ticket_grabbing_render(
     };
 
     // 左侧可选票种列表
-    let available_tickets: Vec<&str> = TICKET_IDS
-        .lock()
-        .unwrap()
-        .iter()
-        .map(|(name, _)| *name)
-        .collect();
+    let available_tickets = ZONES;
 
     let left_items: Vec<Line> = available_tickets
         .iter()
         .enumerate()
         .filter(|(i, _)| !timed_state.selected_tickets.contains(i))
         .enumerate()
-        .map(|(filtered_idx, (original_idx, item))| {
+        .map(|(filtered_idx, (_, item))| {
             if !timed_state.is_right_panel && filtered_idx == timed_state.selected_index {
                 Line::from(*item).style(Style::default().fg(Color::Black).bg(Color::White))
             } else {
@@ -316,7 +247,7 @@ pub fn handle_start_ticket_grabbing_input(
                     timed_state.selected_tickets.len().saturating_sub(1)
                 } else {
                     // 计算实际可选的票种数量
-                    (0..8)
+                    (0..ZONES.len())
                         .filter(|i| !timed_state.selected_tickets.contains(i))
                         .count()
                         .saturating_sub(1)
@@ -352,20 +283,10 @@ pub fn handle_start_ticket_grabbing_input(
             if timed_state.is_button_mode {
                 match timed_state.button_focus {
                     0 => {
-                        // 开始抢票逻辑
+                        // 添加定时任务，到时间后自动抢票
                         if !timed_state.selected_tickets.is_empty() {
-                            gui_state.add_console_message("开始抢票...".to_string());
-
                             let selected_tickets = timed_state.selected_tickets.clone();
-                            let console_sender = gui_state.console_sender.clone();
-                            tokio::spawn(async move {
-                                start_ticket_grabbing_logic(
-                                    read_saved_token().unwrap(),
-                                    selected_tickets,
-                                    console_sender,
-                                )
-                                .await;
-                            });
+                            add_job(gui_state, &selected_tickets);
                         } else {
                             gui_state.add_console_message("请先选择要抢的票种！".to_string());
                         }
@@ -381,7 +302,7 @@ pub fn handle_start_ticket_grabbing_input(
             } else {
                 if !timed_state.is_right_panel {
                     // 获取实际可选的票种（排除已选择的）
-                    let available_tickets: Vec<usize> = (0..8)
+                    let available_tickets: Vec<usize> = (0..ZONES.len())
                         .filter(|i| !timed_state.selected_tickets.contains(i))
                         .collect();
 
@@ -400,7 +321,7 @@ pub fn handle_start_ticket_grabbing_input(
                             timed_state.is_right_panel = true; // 自动切换到右侧面板
                         } else if timed_state.selected_index >= available_tickets.len() - 1 {
                             // 否则调整选择索引
-                            timed_state.selected_index = available_tickets.len() - 2.max(0);
+                            timed_state.selected_index = available_tickets.len() - 2;
                         }
                     }
                 } else {
@@ -428,195 +349,58 @@ pub fn handle_start_ticket_grabbing_input(
     }
 }
 
-// 抢票页面逻辑
-pub async fn start_ticket_grabbing_logic(
-    auth_token: String,
-    selected_tickets: Vec<usize>,
-    console_sender: tokio::sync::mpsc::Sender<String>,
-) {
+// 根据定时页面设置的时间和选择的票种添加定时任务
+fn add_job(gui_state: &mut GuiState, selected_tickets: &[usize]) {
     use crate::Gui::timed_ticket_grabbing::{read_saved_date, read_saved_time};
-    use chrono::{Local, NaiveDateTime};
-    use tokio::time::{sleep, Duration};
-
-    // 获取保存的日期和时间
-    let target_date = match read_saved_date() {
-        Some(date) => date,
-        None => {
-            let _ = console_sender.send("未设置抢票日期！".to_string()).await;
-            return;
-        }
-    };
 
-    let target_time = match read_saved_time() {
-        Some((h, m, s)) => (h, m, s),
-        None => {
-            let _ = console_sender.send("未设置抢票时间！".to_string()).await;
-            return;
-        }
+    let target = match (read_saved_date(), read_saved_time()) {
+        (Some(date), Some((h, m, s))) => date.and_hms_opt(h as u32, m as u32, s as u32),
+        _ => None,
     };
-
-    // 构建目标时间
-    let target_datetime = NaiveDateTime::new(
-        target_date,
-        chrono::NaiveTime::from_hms_opt(
-            target_time.0 as u32,
-            target_time.1 as u32,
-            target_time.2 as u32,
-        )
-        .unwrap(),
-    );
-
-    let now = Local::now().naive_local();
-    if target_datetime <= now {
-        let _ = console_sender
-            .send("错误：设置的时间已经过期，请重新设置！".to_string())
-            .await;
+    let Some(target) = target else {
+        gui_state.add_console_message("请先在定时页面设置抢票日期和时间！".to_string());
         return;
-    }
-
-    let _ = console_sender
-        .send(format!("定时抢票时间设置为: {}", target_datetime))
-        .await;
-
-    // 等待直到目标时间前1秒
-    loop {
-        let now = Local::now().naive_local();
-        if now >= target_datetime {
-            break;
-        }
-
-        let duration = target_datetime - now;
-        let remaining_secs = duration.num_seconds();
-
-        if remaining_secs > 60 {
-            // 大于1分钟时显示分钟
-            if remaining_secs % 60 == 0 {
-                let _ = console_sender
-                    .send(format!("距离抢票还有 {} 分钟", remaining_secs / 60))
-                    .await;
-            }
-        } else {
-            // 最后一分钟显示秒数
-            let _ = console_sender
-                .send(format!("距离抢票还有 {} 秒", remaining_secs))
-                .await;
-        }
-
-        // 根据剩余时间调整睡眠间隔
-        let sleep_duration = if remaining_secs > 60 {
-            Duration::from_secs(1)
-        } else {
-            Duration::from_millis(50) // 最后一分钟更频繁地检查
-        };
-
-        sleep(sleep_duration).await;
-    }
-
-    let _ = console_sender.send("开始发送抢票请求...".to_string()).await;
-
-    // 读取游戏配置
-    let config_path = crate::Gui::Setting::SettingState::get_config_path();
-    let game_configs = match fs::read_to_string(config_path) {
-        Ok(content) => {
-            serde_json::from_str::<HashMap<String, GameConfig>>(&content).unwrap_or_default()
-        }
-        Err(_) => {
-            let _ = console_sender.send("无法读取游戏配置".to_string()).await;
-            return;
-        }
     };
-
-    // 收集所有激活的游戏的communityId
-    let active_community_ids: Vec<String> = game_configs
-        .iter()
-        .filter(|(_, config)| config.active)
-        .map(|(_, config)| config.communityId.clone())
-        .collect();
-
-    if active_community_ids.is_empty() {
-        let _ = console_sender.send("没有选择任何游戏".to_string()).await;
+    if target <= chrono::Local::now().naive_local() {
+        gui_state.add_console_message("错误：设置的时间已经过期，请重新设置！".to_string());
         return;
     }
 
-    // 创建游戏ID到名称的映射
-    let game_names: HashMap<String, String> = game_configs
+    let zones = selected_tickets
         .iter()
-        .map(|(name, config)| (config.communityId.clone(), name.clone()))
+        .filter_map(|idx| ZONES.get(*idx))
+        .map(|name| name.to_string())
         .collect();
 
-    let mut handles = vec![];
-    let mut total_tasks = 0;
-
-    // 首先计算总任务数
-    for community_id in &active_community_ids {
-        let game_name = game_names
-            .get(community_id)
-            .unwrap_or(&"未知游戏".to_string())
-            .clone();
-        let game_config = game_configs.get(&game_name).unwrap().clone();
-
-        for ticket_idx in &selected_tickets {
-            let ticket_name = TICKET_IDS.lock().unwrap()[*ticket_idx].0;
-            if let Some(task_ids) = game_config.red_pack_tasks.get(ticket_name) {
-                total_tasks += task_ids.len();
+    let result = JobList::update(|list| {
+        let id = list.add(Job::new(target, zones));
+        Ok(list.get(id).cloned())
+    });
+    match result {
+        Ok(Some(job)) => {
+            gui_state.add_console_message(format!("已添加定时任务 {}", job.describe()));
+            if gui_state.job_runner.is_none() {
+                gui_state.add_console_message(format!("定时任务#{}将由后台模式执行", job.id));
             }
+            sync_jobs(gui_state);
         }
+        Ok(None) => {}
+        Err(e) => gui_state.add_console_message(format!("保存定时任务失败: {}", e)),
     }
+}
 
-    // 创建barrier等待所有任务就绪
-    let barrier = Arc::new(Barrier::new(total_tasks));
-
-    // 为每个任务创建独立线程
-    for community_id in active_community_ids {
-        let game_name = game_names
-            .get(&community_id)
-            .unwrap_or(&"未知游戏".to_string())
-            .clone();
-        let game_config = game_configs.get(&game_name).unwrap().clone();
-
-        for ticket_idx in &selected_tickets {
-            let ticket_name = TICKET_IDS.lock().unwrap()[*ticket_idx].0;
-
-            if let Some(task_ids) = game_config.red_pack_tasks.get(ticket_name) {
-                let task_ids = task_ids.clone();
-                for task_id in task_ids {
-                    let auth_token = auth_token.clone();
-                    let console_sender = console_sender.clone();
-                    let community_id = community_id.clone();
-                    let game_name = game_name.clone();
-                    let ticket_name = ticket_name.to_string();
-                    let task_id = task_id.clone();
-                    let barrier = barrier.clone();
-
-                    let handle = tokio::spawn(async move {
-                        barrier.wait();
-
-                        match fetch_receive(auth_token, task_id.clone(), community_id).await {
-                            Ok(msg) => {
-                                let _ = console_sender
-                                    .send(format!("{}({}): {}", game_name, ticket_name, msg))
-                                    .await;
-                            }
-                            Err(e) => {
-                                let _ = console_sender
-                                    .send(format!(
-                                        "{}({})抢票失败：{}",
-                                        game_name, ticket_name, e
-                                    ))
-                                    .await;
-                            }
-                        }
-                    });
-                    handles.push(handle);
-                }
+// 按任务列表启动新增或修改过的任务，取消已删除的任务，任务在后台等待到开抢时间后独立运行。
+// 后台模式正在运行时任务已经保存，由后台模式下次读取任务列表时执行
+pub fn sync_jobs(gui_state: &mut GuiState) {
+    let Some(runner) = gui_state.job_runner.as_mut() else {
+        return;
+    };
+    match runner.sync() {
+        Ok(started) => {
+            for job in started {
+                gui_state.add_console_message(format!("已加载定时任务 {}", job.describe()));
             }
         }
+        Err(e) => gui_state.add_console_message(format!("读取定时任务失败: {}", e)),
     }
-
-    // 等待所有任务完成
-    for handle in handles {
-        let _ = handle.await;
-    }
-
-    let _ = console_sender.send("抢票任务已完成".to_string()).await;
 }
@@ -1,17 +1,24 @@
+use crate::Gui::start_grabbing_tickets::sync_jobs;
 use crate::Gui::Gui::render_common_layout;
 use crate::GuiState;
-use chrono::{Datelike, NaiveDate};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 use crossterm::event::KeyCode;
-use dirs;
 use ratatui::prelude::*;
 use ratatui::widgets::{
     calendar::{CalendarEventStore, Monthly},
-    Block, Borders, Padding, Paragraph,
+    Block, Borders, List, Padding, Paragraph,
 };
 use ratatui::Frame;
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use std::time::{Duration, Instant};
 use time::{Date, Month, OffsetDateTime};
+use UTermux::config::{self, Section, UiPrefs};
+use UTermux::engine::jobs::{Job, JobList, JobStatus, Repeat};
+
+// 日历中预览的开抢次数
+const PREVIEW_OCCURRENCES: usize = 10;
+
+// 定时任务列表的刷新间隔
+const JOBS_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
 
 // 添加日期相关的状态
 pub struct TimedGrabbingState {
@@ -24,6 +31,11 @@ pub struct TimedGrabbingState {
     pub selected_time: Option<(u8, u8, u8)>, // 保存选中的时分秒
     pub time_cursor: usize,                  // 0=时, 1=分, 2=秒
     pub is_time_focused: bool,               // 添加此字段表示当前是否在时间选择器上
+    pub is_job_focused: bool,                // 当前是否在定时任务列表上
+    pub job_index: usize,                    // 定时任务列表中选中的任务
+    pub jobs: JobList,                       // 定时任务列表
+    pub jobs_loaded_at: Instant,             // 上次读取任务列表的时间
+    pub jobs_error: Option<String>,          // 读取任务列表失败的原因，失败时不修改任务
 }
 
 impl Default for TimedGrabbingState {
@@ -32,8 +44,12 @@ impl Default for TimedGrabbingState {
         let saved_date = read_saved_date();
         let saved_time = read_saved_time().or_else(|| {
             // 如果没有保存的时间，使用当前系统时间
-            Some((now.hour() as u8, now.minute() as u8, now.second() as u8))
+            Some((now.hour(), now.minute(), now.second()))
         });
+        let (jobs, jobs_error) = match JobList::load() {
+            Ok(jobs) => (jobs, None),
+            Err(e) => (JobList::default(), Some(e.to_string())),
+        };
 
         Self {
             selected_date: saved_date,
@@ -45,68 +61,34 @@ impl Default for TimedGrabbingState {
             selected_time: saved_time,
             time_cursor: 0,
             is_time_focused: false,
+            is_job_focused: false,
+            job_index: 0,
+            jobs,
+            jobs_loaded_at: Instant::now(),
+            jobs_error,
         }
     }
 }
 
-// 保存日期和时间到文件
-pub fn save_date(date: &NaiveDate, time: Option<(u8, u8, u8)>) -> std::io::Result<()> {
-    // 确保目录存在
-    let config_dir = dirs::config_dir();
-    let app_config_dir = config_dir.unwrap().join("UTermux");
-    if let Some(parent) = app_config_dir.parent() {
-        fs::create_dir_all(parent)?;
-    }
-
-    let mut file = OpenOptions::new()
-        .write(true)
-        .create(true)
-        .truncate(true)
-        .open(app_config_dir.join("saved_date"))?;
-
-    let datetime_str = match time {
-        Some((h, m, s)) => format!("{} {:02}:{:02}:{:02}", date.format("%Y-%m-%d"), h, m, s),
-        None => date.format("%Y-%m-%d").to_string(),
-    };
-    file.write_all(datetime_str.as_bytes())?;
-    Ok(())
+// 保存日期和时间到配置文件
+pub fn save_date(date: &NaiveDate, time: Option<(u8, u8, u8)>) -> UTermux::error::Result<()> {
+    config::update(Section::Ui, |prefs: &mut UiPrefs| {
+        prefs.date = Some(*date);
+        prefs.time =
+            time.and_then(|(h, m, s)| NaiveTime::from_hms_opt(h as u32, m as u32, s as u32));
+        Ok(())
+    })
 }
 
-// 从文件读取保存的日期
+// 从配置文件读取保存的日期
 pub fn read_saved_date() -> Option<NaiveDate> {
-    let date_path = dirs::config_dir()
-        .unwrap()
-        .join("UTermux")
-        .join("saved_date");
-    let contents = fs::read_to_string(date_path).unwrap_or_default();
-    // 分割字符串，只取日期部分
-    let date_str = contents.split_whitespace().next()?;
-    NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()
+    config::load::<UiPrefs>(Section::Ui).ok()?.date
 }
 
-// 从文件读取保存的时间
+// 从配置文件读取保存的时间
 pub fn read_saved_time() -> Option<(u8, u8, u8)> {
-    let date_path = dirs::config_dir()
-        .unwrap()
-        .join("UTermux")
-        .join("saved_date");
-    fs::read_to_string(date_path).ok().and_then(|contents| {
-        let parts: Vec<&str> = contents.trim().split(' ').collect();
-        if parts.len() > 1 {
-            let time_parts: Vec<&str> = parts[1].split(':').collect();
-            if time_parts.len() == 3 {
-                Some((
-                    time_parts[0].parse().unwrap_or(0),
-                    time_parts[1].parse().unwrap_or(0),
-                    time_parts[2].parse().unwrap_or(0),
-                ))
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    })
+    let time = config::load::<UiPrefs>(Section::Ui).ok()?.time?;
+    Some((time.hour() as u8, time.minute() as u8, time.second() as u8))
 }
 
 pub fn timed_ticket_grabbing_render(
@@ -121,13 +103,16 @@ pub fn timed_ticket_grabbing_render(
         .constraints(vec![
             Constraint::Length(4), // 日期输入/显示区域
             Constraint::Min(10),   // 日历区域
-            Constraint::Min(1),    // 其他内容区域
+            Constraint::Min(5),    // 定时任务列表
         ])
         .split(content_area);
 
     // 显示日期输入框或已选择的日期
     let date_text = if timed_state.is_editing {
-        format!("{}_", timed_state.input_buffer)
+        format!(
+            "Cron表达式（秒 分 时 日 月 周）: {}_",
+            timed_state.input_buffer
+        )
     } else {
         match (timed_state.selected_date, timed_state.selected_time) {
             (Some(date), Some((h, m, s))) => {
@@ -162,14 +147,80 @@ pub fn timed_ticket_grabbing_render(
 
         draw_calendar(frame, split[0], timed_state);
         render_time_selector(frame, split[1], timed_state);
+        render_job_list(frame, layout[2], timed_state);
     }
 }
 
+// 定时任务列表渲染
+fn render_job_list(frame: &mut Frame, area: Rect, timed_state: &mut TimedGrabbingState) {
+    // 任务状态由后台任务更新，定期重新读取
+    if timed_state.jobs_loaded_at.elapsed() > JOBS_REFRESH_INTERVAL {
+        timed_state.reload_jobs();
+    }
+
+    let items: Vec<Line> = if let Some(e) = &timed_state.jobs_error {
+        vec![Line::from(format!("读取定时任务失败，请修复配置文件: {}", e)).red()]
+    } else if timed_state.jobs.jobs.is_empty() {
+        vec![Line::from("暂无定时任务，在开始抢票页面选择票种后添加")]
+    } else {
+        timed_state
+            .jobs
+            .jobs
+            .iter()
+            .enumerate()
+            .map(|(i, job)| {
+                let line = Line::from(job.describe());
+                if timed_state.is_job_focused && i == timed_state.job_index {
+                    line.style(Style::default().fg(Color::Black).bg(Color::White))
+                } else {
+                    line
+                }
+            })
+            .collect()
+    };
+
+    let list = List::new(items).block(
+        Block::default()
+            .title("定时任务（d: 删除  r: 切换重复  c: 设置cron）")
+            .borders(Borders::ALL)
+            .border_style(if timed_state.is_job_focused {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default()
+            }),
+    );
+    frame.render_widget(list, area);
+}
+
 // 日历渲染函数
 fn draw_calendar(frame: &mut Frame, area: Rect, timed_state: &TimedGrabbingState) {
     let calendar_area = area;
     let mut list = CalendarEventStore::default();
 
+    // 预览定时任务接下来的开抢日期：选中任务时显示该任务，否则显示所有等待中的任务
+    let previews: Vec<NaiveDateTime> = if timed_state.is_job_focused {
+        timed_state
+            .jobs
+            .jobs
+            .get(timed_state.job_index)
+            .map(|job| job.occurrences(PREVIEW_OCCURRENCES))
+            .unwrap_or_default()
+    } else {
+        timed_state
+            .jobs
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Pending)
+            .flat_map(|job| job.occurrences(PREVIEW_OCCURRENCES))
+            .collect()
+    };
+    for date in previews
+        .iter()
+        .filter_map(|time| to_calendar_date(time.date()))
+    {
+        list.add(date, Style::default().fg(Color::Black).bg(Color::Yellow));
+    }
+
     // 显示光标位置
     list.add(
         timed_state.cursor_date,
@@ -179,16 +230,9 @@ fn draw_calendar(frame: &mut Frame, area: Rect, timed_state: &TimedGrabbingState
     );
 
     // 显示已选择的日期（如果有）
-    if let Some(selected_date) = timed_state.selected_date {
-        let selected_time = Date::from_calendar_date(
-            selected_date.year_ce().1 as i32,
-            Month::try_from(selected_date.month() as u8).unwrap(),
-            selected_date.day() as u8,
-        )
-        .unwrap();
-
+    if let Some(selected_date) = timed_state.selected_date.and_then(to_calendar_date) {
         list.add(
-            selected_time,
+            selected_date,
             Style::default()
                 .add_modifier(Modifier::BOLD)
                 .bg(Color::Green),
@@ -290,6 +334,13 @@ pub fn handle_timed_input(
     key: KeyCode,
 ) {
     match key {
+        KeyCode::Enter if timed_state.is_editing => {
+            // 确认输入的 cron 表达式
+            let repeat = Repeat::Cron(timed_state.input_buffer.trim().to_string());
+            update_selected_job(state, timed_state, |job, now| job.set_repeat(repeat, now));
+            timed_state.is_editing = false;
+            timed_state.input_buffer.clear();
+        }
         KeyCode::Enter => {
             if !timed_state.is_editing {
                 let selected_date = NaiveDate::from_ymd_opt(
@@ -316,13 +367,35 @@ pub fn handle_timed_input(
             }
         }
         KeyCode::Tab => {
+            // 在日历、时间选择器和任务列表之间切换
             if !timed_state.is_editing {
-                timed_state.is_time_focused = !timed_state.is_time_focused;
+                if timed_state.is_job_focused {
+                    timed_state.is_job_focused = false;
+                } else if timed_state.is_time_focused {
+                    timed_state.is_time_focused = false;
+                    timed_state.is_job_focused = true;
+                    timed_state.reload_jobs();
+                } else {
+                    timed_state.is_time_focused = true;
+                }
             }
         }
         KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
             if !timed_state.is_editing {
-                if timed_state.is_time_focused {
+                if timed_state.is_job_focused {
+                    // 任务列表模式
+                    match key {
+                        KeyCode::Up => {
+                            timed_state.job_index = timed_state.job_index.saturating_sub(1)
+                        }
+                        KeyCode::Down => {
+                            if timed_state.job_index + 1 < timed_state.jobs.jobs.len() {
+                                timed_state.job_index += 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                } else if timed_state.is_time_focused {
                     // 时间选择模式
                     match key {
                         KeyCode::Up => {
@@ -447,6 +520,29 @@ pub fn handle_timed_input(
                 state.current_page = crate::function_list::Main;
             }
         }
+        KeyCode::Char('d') if timed_state.is_job_focused && !timed_state.is_editing => {
+            delete_job(state, timed_state);
+        }
+        KeyCode::Char('r') if timed_state.is_job_focused && !timed_state.is_editing => {
+            // 单次 -> 每天 -> 每周（开抢日期对应的星期） -> 单次
+            update_selected_job(state, timed_state, |job, now| {
+                let repeat = match job.repeat {
+                    Repeat::Once => Repeat::Daily,
+                    Repeat::Daily => Repeat::Weekly(vec![job.target.weekday()]),
+                    _ => Repeat::Once,
+                };
+                job.set_repeat(repeat, now)
+            });
+        }
+        KeyCode::Char('c') if timed_state.is_job_focused && !timed_state.is_editing => {
+            if let Some(job) = timed_state.jobs.jobs.get(timed_state.job_index) {
+                timed_state.input_buffer = match &job.repeat {
+                    Repeat::Cron(expr) => expr.clone(),
+                    _ => job.target.format("%S %M %H * * *").to_string(),
+                };
+                timed_state.is_editing = true;
+            }
+        }
         KeyCode::Char(c) => {
             if timed_state.is_editing {
                 timed_state.input_buffer.push(c);
@@ -461,9 +557,109 @@ pub fn handle_timed_input(
     }
 }
 
+// 任务列表读取失败时不修改任务，以免覆盖配置文件中的任务
+fn check_jobs_loaded(state: &mut GuiState, timed_state: &TimedGrabbingState) -> bool {
+    match &timed_state.jobs_error {
+        Some(e) => {
+            state.add_console_message(format!("读取定时任务失败，请先修复配置文件: {}", e));
+            false
+        }
+        None => true,
+    }
+}
+
+// 删除选中的定时任务，正在等待的任务会被取消
+fn delete_job(state: &mut GuiState, timed_state: &mut TimedGrabbingState) {
+    if !check_jobs_loaded(state, timed_state) {
+        return;
+    }
+    let Some(id) = timed_state
+        .jobs
+        .jobs
+        .get(timed_state.job_index)
+        .map(|job| job.id)
+    else {
+        return;
+    };
+
+    match JobList::update(|list| Ok(list.remove(id))) {
+        Ok(Some(_)) => state.add_console_message(format!("已删除定时任务#{}", id)),
+        Ok(None) => {}
+        Err(e) => state.add_console_message(format!("删除定时任务失败: {}", e)),
+    }
+    sync_jobs(state);
+    timed_state.reload_jobs();
+}
+
+// 修改选中的定时任务并保存，等待中的任务按新的设置重新启动
+fn update_selected_job<F>(state: &mut GuiState, timed_state: &mut TimedGrabbingState, update: F)
+where
+    F: FnOnce(&mut Job, NaiveDateTime) -> UTermux::error::Result<()>,
+{
+    if !check_jobs_loaded(state, timed_state) {
+        return;
+    }
+    let Some(id) = timed_state
+        .jobs
+        .jobs
+        .get(timed_state.job_index)
+        .map(|job| job.id)
+    else {
+        return;
+    };
+
+    // 修改失败时不保存
+    let result = JobList::update(|list| {
+        let Some(job) = list.jobs.iter_mut().find(|job| job.id == id) else {
+            return Ok(None);
+        };
+        update(job, Local::now().naive_local())?;
+        let job = job.clone();
+        list.jobs.sort_by_key(|job| job.target);
+        Ok(Some(job))
+    });
+    let job = match result {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            state.add_console_message(format!("修改定时任务失败: {}", e));
+            return;
+        }
+    };
+
+    state.add_console_message(format!("已修改定时任务 {}", job.describe()));
+    sync_jobs(state);
+    timed_state.reload_jobs();
+}
+
+impl TimedGrabbingState {
+    // 重新读取任务列表，读取失败时保留上次的列表并记录原因
+    pub fn reload_jobs(&mut self) {
+        match JobList::load() {
+            Ok(jobs) => {
+                self.jobs = jobs;
+                self.jobs_error = None;
+            }
+            Err(e) => self.jobs_error = Some(e.to_string()),
+        }
+        self.jobs_loaded_at = Instant::now();
+        self.job_index = self.job_index.min(self.jobs.jobs.len().saturating_sub(1));
+    }
+}
+
+// chrono 日期转换为日历使用的日期
+fn to_calendar_date(date: NaiveDate) -> Option<Date> {
+    Date::from_calendar_date(
+        date.year(),
+        Month::try_from(date.month() as u8).ok()?,
+        date.day() as u8,
+    )
+    .ok()
+}
+
 // 添加一个辅助函数来获取月份的最后一天
 fn get_last_day_of_month(year: i32, month: Month) -> u8 {
-    (if month == Month::December {
+    if month == Month::December {
         Date::from_calendar_date(year + 1, Month::January, 1)
     } else {
         Date::from_calendar_date(year, month.next(), 1)
@@ -471,7 +667,7 @@ fn get_last_day_of_month(year: i32, month: Month) -> u8 {
     .unwrap()
     .previous_day()
     .unwrap()
-    .day()) as u8
+    .day()
 }
 
 #[tokio::test]
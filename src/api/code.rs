@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+// 业务码对应的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Success,          // 领取成功
+    PermanentFailure, // 已抢完、已领取、不满足条件，不再重试
+    RetryNow,         // 立即重试
+    RetryLater,       // 被限流，退避后重试
+    AuthFailure,      // token失效
+    RiskControl,      // 触发风控，停止该账号
+    Unknown,          // 未收录的业务码，按可重试处理
+}
+
+pub struct CodeInfo {
+    pub code: &'static str,
+    pub outcome: Outcome,
+    pub desc: &'static str,
+    pub observed: bool, // 是否在真实响应中见过，其余是按常见约定推测的，遇到后需要核实
+}
+
+// 已知的业务码，只有 2040 有出处（原版领券逻辑按它判断已抢完），其余均为推测
+pub const CATALOGUE: &[CodeInfo] = &[
+    CodeInfo {
+        code: "0000",
+        outcome: Outcome::Success,
+        desc: "成功",
+        observed: false,
+    },
+    CodeInfo {
+        code: "200",
+        outcome: Outcome::Success,
+        desc: "成功",
+        observed: false,
+    },
+    CodeInfo {
+        code: "1001",
+        outcome: Outcome::AuthFailure,
+        desc: "未登录或登录已失效",
+        observed: false,
+    },
+    CodeInfo {
+        code: "1002",
+        outcome: Outcome::AuthFailure,
+        desc: "authToken无效",
+        observed: false,
+    },
+    CodeInfo {
+        code: "2030",
+        outcome: Outcome::RetryNow,
+        desc: "活动未开始",
+        observed: false,
+    },
+    CodeInfo {
+        code: "2040",
+        outcome: Outcome::PermanentFailure,
+        desc: "已领取或已抢完",
+        observed: true,
+    },
+    CodeInfo {
+        code: "2041",
+        outcome: Outcome::PermanentFailure,
+        desc: "已达到领取上限",
+        observed: false,
+    },
+    CodeInfo {
+        code: "2042",
+        outcome: Outcome::PermanentFailure,
+        desc: "不满足领取条件",
+        observed: false,
+    },
+    CodeInfo {
+        code: "2043",
+        outcome: Outcome::PermanentFailure,
+        desc: "活动已结束",
+        observed: false,
+    },
+    CodeInfo {
+        code: "429",
+        outcome: Outcome::RetryLater,
+        desc: "请求过于频繁",
+        observed: false,
+    },
+    CodeInfo {
+        code: "9001",
+        outcome: Outcome::RetryLater,
+        desc: "操作太频繁，请稍后再试",
+        observed: false,
+    },
+    CodeInfo {
+        code: "9002",
+        outcome: Outcome::RiskControl,
+        desc: "账号存在风险，已被限制",
+        observed: false,
+    },
+    CodeInfo {
+        code: "500",
+        outcome: Outcome::RetryNow,
+        desc: "系统繁忙",
+        observed: false,
+    },
+    CodeInfo {
+        code: "9999",
+        outcome: Outcome::RetryNow,
+        desc: "系统异常",
+        observed: false,
+    },
+];
+
+pub fn lookup(code: &str) -> Option<&'static CodeInfo> {
+    CATALOGUE.iter().find(|info| info.code == code)
+}
+
+// 对业务码分类
+pub fn classify(code: &str) -> Outcome {
+    lookup(code).map_or(Outcome::Unknown, |info| info.outcome)
+}
+
+// 一个任务运行中遇到的未收录业务码：业务码 -> 原始提示，同一任务的所有请求共享
+#[derive(Debug, Clone, Default)]
+pub struct UnknownCodes(Arc<Mutex<BTreeMap<String, String>>>);
+
+impl UnknownCodes {
+    // 只记录未收录的业务码，同一业务码保留第一次的提示
+    pub fn record(&self, code: &str, msg: &str) {
+        if lookup(code).is_none() {
+            self.0
+                .lock()
+                .unwrap()
+                .entry(code.to_string())
+                .or_insert_with(|| msg.to_string());
+        }
+    }
+
+    // 取出并清空记录的业务码，按业务码排序
+    pub fn take(&self) -> Vec<(String, String)> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+            .into_iter()
+            .collect()
+    }
+}
+
+#[test]
+fn test_classify() {
+    assert_eq!(classify("2040"), Outcome::PermanentFailure);
+    assert_eq!(classify("9001"), Outcome::RetryLater);
+    assert_eq!(classify("1001"), Outcome::AuthFailure);
+    assert_eq!(classify("7777"), Outcome::Unknown);
+
+    let unknown = UnknownCodes::default();
+    unknown.record("7777", "神秘错误");
+    unknown.record("7777", "另一条提示");
+    unknown.record("2040", "优惠券已抢完");
+    assert_eq!(
+        unknown.take(),
+        vec![("7777".to_string(), "神秘错误".to_string())]
+    );
+    assert!(unknown.take().is_empty());
+}
@@ -1,6 +1,7 @@
-use crate::api::queryMobilePhone::read_saved_token;
-use crate::utils::request::{self, Headers, LoginData};
-use serde::{Deserialize, Serialize};
+use crate::api::{parse_response, ApiResponse};
+use crate::error::Result;
+use crate::utils::request::{Headers, LoginData, RequestContext};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::path::PathBuf;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -11,11 +12,6 @@ pub struct InfoResponse {
     pub success: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct InfoData {
-    object: InfoObject,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InfoObject {
     pub zoneRedList: Vec<ZoneRed>,
@@ -25,50 +21,202 @@ pub struct InfoObject {
 pub struct ZoneRed {
     redList: Vec<RedItem>,
     zoneName: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    zoneId: Option<i64>,
 }
 
+// 接口返回的数值有时是数字有时是字符串，统一按可选数值解析
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RedItem {
     redPackTaskId: i64,
+    #[serde(default, alias = "redPackAmount", deserialize_with = "lenient_number")]
+    amount: Option<f64>,
+    #[serde(default, alias = "useThreshold", deserialize_with = "lenient_number")]
+    fullAmount: Option<f64>,
+    #[serde(default, alias = "remainNum", deserialize_with = "lenient_number")]
+    stock: Option<f64>,
+    #[serde(default, alias = "beginTime")]
+    startTime: Option<String>,
+    #[serde(default, alias = "perLimit", deserialize_with = "lenient_number")]
+    limitNum: Option<f64>,
+}
+
+fn lenient_number<'de, D>(deserializer: D) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+// 领券中心
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CouponCenter {
+    pub zones: Vec<Zone>,
+}
+
+// 专区（置顶专区、新人专区、每周专区、每日专区）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    pub id: Option<i64>, // 领券时提交的 zoneId，接口未返回时为空
+    pub tasks: Vec<RedPackTask>,
+}
+
+// 专区下的一张优惠券
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RedPackTask {
+    pub id: i64,                     // redPackTaskId
+    pub amount: Option<f64>,         // 优惠券金额
+    pub threshold: Option<f64>,      // 使用门槛
+    pub stock: Option<i64>,          // 剩余库存
+    pub start_time: Option<String>,  // 开抢时间
+    pub per_user_limit: Option<i64>, // 每人限领
+}
+
+impl CouponCenter {
+    pub fn zone(&self, name: &str) -> Option<&Zone> {
+        self.zones.iter().find(|zone| zone.name == name)
+    }
+}
+
+impl Zone {
+    pub fn task_ids(&self) -> Vec<i64> {
+        self.tasks.iter().map(|task| task.id).collect()
+    }
+}
+
+impl From<InfoObject> for CouponCenter {
+    fn from(info: InfoObject) -> Self {
+        let zones = info
+            .zoneRedList
+            .into_iter()
+            .map(|zone| Zone {
+                name: zone.zoneName,
+                id: zone.zoneId,
+                tasks: zone.redList.into_iter().map(RedPackTask::from).collect(),
+            })
+            .collect();
+        CouponCenter { zones }
+    }
 }
 
+impl From<RedItem> for RedPackTask {
+    fn from(item: RedItem) -> Self {
+        RedPackTask {
+            id: item.redPackTaskId,
+            amount: item.amount,
+            threshold: item.fullAmount,
+            stock: item.stock.map(|n| n as i64),
+            start_time: item.startTime,
+            per_user_limit: item.limitNum.map(|n| n as i64),
+        }
+    }
+}
+
+// 获取领券中心数据
 pub async fn get_info(
+    ctx: &RequestContext,
     auth_token: String,
     id: String,
-) -> Result<String, Box<dyn std::error::Error>> {
+) -> Result<CouponCenter> {
     let data = LoginData { id: id.to_string() };
-    let headers = Some(Headers {
-        auth_token,
-        user_agent: None,
-    });
-
-    let response = request::request(
-        PathBuf::from("community/coupon/center/info"),
-        Some(data),
-        headers,
-    )
-    .await
-    .unwrap();
-
-    let info: InfoResponse = serde_json::from_str(&response)?;
-
-    // 收集所有的 redPackTaskId 和 zoneName
-    let mut result = Vec::new();
-    for zone in &info.object.zoneRedList {
-        let mut ids = Vec::new();
-        for red_item in &zone.redList {
-            ids.push(red_item.redPackTaskId.to_string());
-        }
-        // 将zoneName和对应的ID列表组合
-        result.push(format!("{}:{}", zone.zoneName, ids.join(",")));
-    }
+    let headers = Headers::token(auth_token);
+
+    let response = ctx
+        .request(
+            PathBuf::from("community/coupon/center/info"),
+            Some(data),
+            Some(&headers),
+        )
+        .await?;
 
-    Ok(result.join(";")) // 用分号分隔不同分类，分类名和ID之间用冒号分隔
+    parse_response::<ApiResponse>(&response)?.check()?;
+    let info: InfoResponse = parse_response(&response)?;
+    Ok(info.object.into())
 }
 
 #[tokio::test]
 async fn test_get_info() {
-    let auth_token = read_saved_token().unwrap();
-    let red_pack_task_id = get_info(auth_token, "7".to_string()).await.unwrap();
-    println!("RedPackTaskId: {}", red_pack_task_id);
+    use crate::error::Error;
+    use crate::utils::mock_server::{MockResponse, MockServer, COUPON_INFO};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let center = get_info(&ctx, "test-token".to_string(), "7".to_string())
+        .await
+        .unwrap();
+    let names: Vec<&str> = center.zones.iter().map(|zone| zone.name.as_str()).collect();
+    assert_eq!(names, vec!["置顶专区", "新人专区", "每周专区", "每日专区"]);
+    assert_eq!(
+        center.zone("每周专区").unwrap().task_ids(),
+        vec![58238, 58237]
+    );
+    assert!(center.zone("新人专区").unwrap().tasks.is_empty());
+    // 未返回 zoneId 时不编造
+    assert_eq!(center.zone("每日专区").unwrap().id, None);
+    assert_eq!(
+        server.requests_to(COUPON_INFO)[0].json().unwrap()["id"],
+        "7"
+    );
+
+    // 非JSON响应返回错误
+    server.respond(COUPON_INFO, vec![MockResponse::not_json()]);
+    assert!(matches!(
+        get_info(&ctx, "test-token".to_string(), "7".to_string()).await,
+        Err(Error::InvalidResponse(_))
+    ));
+
+    // token过期
+    server.respond(COUPON_INFO, vec![MockResponse::expired_token()]);
+    assert!(matches!(
+        get_info(&ctx, "test-token".to_string(), "7".to_string()).await,
+        Err(Error::TokenExpired)
+    ));
+}
+
+#[tokio::test]
+async fn test_get_info_task_fields() {
+    use crate::utils::mock_server::{MockResponse, MockServer, COUPON_INFO};
+    use serde_json::json;
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    server.respond(
+        COUPON_INFO,
+        vec![MockResponse::success(json!({
+            "zoneRedList": [{
+                "zoneName": "每日专区",
+                "zoneId": 9,
+                "redList": [{
+                    "redPackTaskId": 59623,
+                    "amount": "5.00",
+                    "fullAmount": 30,
+                    "stock": 120,
+                    "startTime": "2026-10-19 10:00:00",
+                    "limitNum": 1
+                }]
+            }]
+        }))],
+    );
+
+    let center = get_info(&ctx, "test-token".to_string(), "14".to_string())
+        .await
+        .unwrap();
+    let zone = center.zone("每日专区").unwrap();
+    assert_eq!(zone.id, Some(9));
+    assert_eq!(
+        zone.tasks[0],
+        RedPackTask {
+            id: 59623,
+            amount: Some(5.0),
+            threshold: Some(30.0),
+            stock: Some(120),
+            start_time: Some("2026-10-19 10:00:00".to_string()),
+            per_user_limit: Some(1),
+        }
+    );
 }
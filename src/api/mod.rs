@@ -1,9 +1,14 @@
+use crate::error::{Error, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{self, json};
 use std::sync::LazyLock;
+pub mod code;
 pub mod info;
 pub mod queryMobilePhone;
 pub mod receive;
+pub mod strategy;
+pub mod warmup;
 
 // 接口返回数据结构
 #[derive(Serialize, Deserialize)]
@@ -13,6 +18,22 @@ pub struct ApiResponse {
     responseMsg: String,
 }
 
+impl ApiResponse {
+    // 业务失败时转换为对应的错误
+    pub fn check(self) -> Result<()> {
+        if self.success {
+            Ok(())
+        } else {
+            Err(Error::business(self.responseCode, self.responseMsg))
+        }
+    }
+}
+
+// 解析接口响应，不是预期的JSON时返回原始响应体
+pub fn parse_response<T: DeserializeOwned>(response: &str) -> Result<T> {
+    serde_json::from_str(response).map_err(|_| Error::InvalidResponse(response.to_string()))
+}
+
 // 优惠券id
 pub static redPackTaskIdVec: LazyLock<serde_json::Value> = LazyLock::new(|| {
     json!(
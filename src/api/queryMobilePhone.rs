@@ -1,7 +1,12 @@
-use crate::utils::request::{request, Headers};
-use dirs;
+use crate::api::{parse_response, ApiResponse};
+use crate::config::{self, Section};
+use crate::error::{Error, Result};
+use crate::utils::request::{Headers, RequestContext};
+use crate::utils::persist::FileLock;
+use crate::utils::vault::{self, Sealed, Vault};
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Serialize)]
@@ -17,48 +22,172 @@ struct PhoneObject {
 
 #[derive(Deserialize, Debug)]
 struct PhoneResponse {
-    success: bool,
-    object: PhoneObject,
+    object: Option<PhoneObject>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TokenRecord {
     pub auth_token: String,
     pub mobile_phone: String,
     pub user_agent: String,
     pub active: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>, // 该账号的其他设备指纹请求头，例如 deviceId
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid: Option<bool>, // 最近一次校验token的结果，未校验时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_validated_at: Option<NaiveDateTime>, // 最近一次校验的时间
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>, // 账号备注
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl TokenRecord {
+    // 该账号发请求使用的请求头
+    pub fn headers(&self) -> Headers {
+        Headers::new(&self.auth_token, &self.user_agent).with_extra(self.headers.clone())
+    }
+
+    // 列表中显示的名称：有备注时显示备注和手机号
+    pub fn label(&self) -> String {
+        match &self.alias {
+            Some(alias) => format!("{} ({})", alias, self.mobile_phone),
+            None => self.mobile_phone.clone(),
+        }
+    }
+
+    // 换上刚校验过的新token，user_agent 为空时沿用原来的
+    pub fn set_token(&mut self, auth_token: &str, user_agent: &str) {
+        self.auth_token = auth_token.to_string();
+        if !user_agent.is_empty() {
+            self.user_agent = user_agent.to_string();
+        }
+        self.valid = Some(true);
+        self.last_validated_at = Some(chrono::Local::now().naive_local());
+    }
+
+    // token状态的显示文字
+    pub fn status(&self) -> &'static str {
+        match self.valid {
+            Some(true) => "有效",
+            Some(false) => "已失效",
+            None => "未校验",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
 pub struct TokenStorage {
     pub records: Vec<TokenRecord>,
 }
 
-// 
-pub fn get_config_path() -> Option<PathBuf> {
-    let config_dir = dirs::config_dir()?;
-    let app_config_dir = config_dir.join("UTermux");
-    Some(app_config_dir.join("auth_token.json"))
+// 账号文件的两种格式：明文的账号列表，或加密后的账号列表
+#[derive(Serialize)]
+#[serde(untagged)]
+enum StoredTokens {
+    Encrypted { encrypted: Sealed },
+    Plain(TokenStorage),
+}
+
+// 读取配置文件中的账号，没有保存过账号时返回空
+fn read_stored() -> Result<Option<StoredTokens>> {
+    let Some(value) = config::load_value(Section::Accounts)? else {
+        return Ok(None);
+    };
+    // 按有没有 encrypted 字段区分，格式错误时能指出具体的字段
+    Ok(Some(match value.get("encrypted") {
+        Some(encrypted) => StoredTokens::Encrypted {
+            encrypted: config::from_value("accounts.encrypted", encrypted.clone())?,
+        },
+        None => StoredTokens::Plain(config::from_value("accounts", value)?),
+    }))
+}
+
+fn write_stored(lock: &FileLock, stored: &impl Serialize) -> Result<()> {
+    config::save_locked(lock, Section::Accounts, stored)
 }
 
-// 保存token
-fn save_token(token: &str, mobile_phone: &str, user_agent: &str) -> Result<(), Box<dyn std::error::Error>> {
-    if let Some(token_path) = get_config_path() {
-        // 确保配置目录存在
-        if let Some(parent) = token_path.parent() {
-            fs::create_dir_all(parent)?;
+impl TokenStorage {
+    // 读取账号列表，加密的文件使用当前会话解锁的密钥解密，文件不存在时返回空列表
+    pub fn load() -> Result<Self> {
+        match read_stored()? {
+            None => Ok(Self::default()),
+            Some(StoredTokens::Plain(storage)) => Ok(storage),
+            Some(StoredTokens::Encrypted { encrypted }) => {
+                let vault = vault::unlocked().ok_or(Error::Locked)?;
+                Ok(serde_json::from_slice(&vault.open(&encrypted)?)?)
+            }
+        }
+    }
+
+    // 已解锁时加密保存；文件已加密但尚未解锁时拒绝保存，避免覆盖成明文
+    fn save_locked(&self, lock: &FileLock) -> Result<()> {
+        match vault::unlocked() {
+            Some(vault) => write_stored(lock, &StoredTokens::Encrypted {
+                encrypted: vault.seal(&serde_json::to_vec(self)?)?,
+            }),
+            None if Self::is_encrypted() => Err(Error::Locked),
+            None => write_stored(lock, self),
         }
+    }
+
+    // 读取、修改并保存账号列表，期间一直持有锁，读取失败时不保存，避免覆盖原文件
+    pub fn update(change: impl FnOnce(&mut Self) -> Result<()>) -> Result<Self> {
+        let lock = config::lock()?;
+        let mut storage = Self::load()?;
+        change(&mut storage)?;
+        storage.save_locked(&lock)?;
+        Ok(storage)
+    }
+
+    pub fn is_encrypted() -> bool {
+        matches!(read_stored(), Ok(Some(StoredTokens::Encrypted { .. })))
+    }
+
+    // 加密的文件是否还需要输入口令
+    pub fn is_locked() -> bool {
+        Self::is_encrypted() && vault::unlocked().is_none()
+    }
+
+    // 用口令解锁加密的账号文件，口令错误时返回错误
+    pub fn unlock(passphrase: &str) -> Result<Self> {
+        let Some(StoredTokens::Encrypted { encrypted }) = read_stored()? else {
+            return Self::load();
+        };
+        let vault = Vault::for_sealed(passphrase, &encrypted)?;
+        let storage = serde_json::from_slice(&vault.open(&encrypted)?)?;
+        vault::set_unlocked(Some(vault));
+        Ok(storage)
+    }
 
-        // 读取或创建存储对象
-        let mut storage = if token_path.exists() {
-            let content = fs::read_to_string(&token_path)?;
-            serde_json::from_str(&content).unwrap_or(TokenStorage { records: vec![] })
-        } else {
-            TokenStorage { records: vec![] }
+    // 把明文的账号文件转换为加密格式
+    pub fn encrypt(passphrase: &str) -> Result<Self> {
+        if Self::is_encrypted() {
+            return Err(Error::Config("账号文件已经加密".to_string()));
+        }
+        // 读取明文和加密写回期间一直持有锁
+        vault::set_unlocked(Some(Vault::new(passphrase)?));
+        let storage = match Self::update(|_| Ok(())) {
+            Ok(storage) => storage,
+            Err(e) => {
+                vault::set_unlocked(None);
+                return Err(e);
+            }
         };
+        // 备份中是明文
+        config::remove_plaintext_copies()?;
+        Ok(storage)
+    }
+}
 
-        // 检查是否存在相同手机号
-        if storage.records.iter().any(|r| r.mobile_phone == mobile_phone) {
+// 保存token，已有同一手机号的账号时替换它的token
+pub fn save_token(token: &str, mobile_phone: &str, user_agent: &str) -> Result<()> {
+    TokenStorage::update(|storage| {
+        if let Some(record) = storage
+            .records
+            .iter_mut()
+            .find(|r| r.mobile_phone == mobile_phone)
+        {
+            record.set_token(token, user_agent);
             return Ok(());
         }
 
@@ -68,92 +197,110 @@ fn save_token(token: &str, mobile_phone: &str, user_agent: &str) -> Result<(), B
             mobile_phone: mobile_phone.to_string(),
             user_agent: user_agent.to_string(),
             active: storage.records.is_empty(), // 第一个记录设置为active
+            headers: BTreeMap::new(),
+            valid: Some(true), // 保存前已经查询过手机号
+            last_validated_at: Some(chrono::Local::now().naive_local()),
+            alias: None,
         });
-
-        // 将对象序列化为JSON并保存
-        let json_content = serde_json::to_string_pretty(&storage)?;
-        fs::write(token_path, json_content)?;
-    }
+        Ok(())
+    })?;
     Ok(())
 }
 
-// 修改read_saved_token函数以适应新的JSON格式
-pub fn read_saved_token() -> Option<String> {
-    get_config_path().and_then(|path| {
-        fs::read_to_string(path).ok().and_then(|content| {
-            let storage: TokenStorage = serde_json::from_str(&content).ok()?;
-            storage
-                .records
-                .iter()
-                .find(|r| r.active)
-                .map(|r| r.auth_token.clone())
-        })
-    })
-}
-
-// 添加新函数来获取当前激活账号的user_agent
-pub fn read_saved_user_agent() -> Option<String> {
-    get_config_path().and_then(|path| {
-        fs::read_to_string(path).ok().and_then(|content| {
-            let storage: TokenStorage = serde_json::from_str(&content).ok()?;
-            storage
-                .records
-                .iter()
-                .find(|r| r.active)
-                .map(|r| r.user_agent.clone())
-        })
-    })
+// 当前激活账号的token
+pub fn read_saved_token() -> Result<Option<String>> {
+    Ok(read_saved_account(None)?.map(|r| r.auth_token))
+}
+
+// 按手机号查找账号，未指定时返回当前激活的账号
+pub fn read_saved_account(mobile_phone: Option<&str>) -> Result<Option<TokenRecord>> {
+    Ok(TokenStorage::load()?.records.into_iter().find(|r| match mobile_phone {
+        Some(mobile_phone) => r.mobile_phone == mobile_phone,
+        None => r.active,
+    }))
+}
+
+// 查询token对应的手机号，不保存token
+pub async fn fetch_mobile_phone(
+    ctx: &RequestContext,
+    auth_token: &str,
+    user_agent: &str,
+) -> Result<String> {
+    fetch_account_phone(ctx, &Headers::new(auth_token, user_agent)).await
+}
+
+// 用账号的完整请求头查询手机号
+pub async fn fetch_account_phone(ctx: &RequestContext, headers: &Headers) -> Result<String> {
+    let response = ctx
+        .request(
+            PathBuf::from("userBase/queryMobilePhone"),
+            None::<PhoneData>,
+            Some(headers),
+        )
+        .await?;
+
+    // 不是json格式时可能是网关错误页，不能说明token失效
+    let value: serde_json::Value = parse_response(&response)?;
+    // 没有 success 字段的是登录失效时返回的响应
+    if value.get("success").is_none() {
+        return Err(Error::TokenExpired);
+    }
+
+    // 按业务码区分token失效和其他失败
+    parse_response::<ApiResponse>(&response)?.check()?;
+    let json: PhoneResponse = parse_response(&response)?;
+    json.object
+        .map(|object| object.mobile_phone)
+        .ok_or(Error::InvalidResponse(response))
 }
 
 // 查询手机号验证是否登录
 pub async fn query_mobile_phone(
+    ctx: &RequestContext,
     auth_token: &str,
     user_agent: &str,
-) -> Result<String, Box<dyn std::error::Error>> {
-    let headers = Headers {
-        auth_token: auth_token.to_string(),
-        user_agent: Some(user_agent.to_string()),
-    };
-    let response = request(
-        PathBuf::from("userBase/queryMobilePhone"),
-        None::<PhoneData>,
-        Some(headers),
-    )
-    .await
-    .unwrap();
-
-    // 如果不是json格式
-    if !response.contains("success") {
-        return Err(Box::new(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "authToken输入错误或已过期".to_string(),
-        )));
-    } else {
-        // 解析JSON响应
-        let json: PhoneResponse = serde_json::from_str(&response)?;
-        // 判断authToken能否登录
-        match json.success {
-            true => {
-                // 保存 token 到配置目录，现在也保存 user_agent
-                save_token(auth_token, &json.object.mobile_phone, user_agent)?;
-
-                Ok(format!(
-                    "{}:{}",
-                    "登录成功!您的手机号是", json.object.mobile_phone
-                ))
-            }
-            false => Err(Box::new(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "authToken过期".to_string(),
-            ))),
-        }
-    }
+) -> Result<String> {
+    let mobile_phone = fetch_mobile_phone(ctx, auth_token, user_agent).await?;
+
+    // 保存 token 到配置目录，现在也保存 user_agent
+    save_token(auth_token, &mobile_phone, user_agent)?;
+
+    Ok(format!("{}:{}", "登录成功!您的手机号是", mobile_phone))
 }
 
 #[tokio::test]
 async fn test_query_mobile_phone() {
-    let auth_token = "d8AZUpWrOsfV1GUfqhPS4EQ08BfnRuJ2xIRU0hrPRCD9l32AHpr5QgqtPysy5y_cLJ5vuDm34Cwj2fltIbFO6HFfVzG85e551gygs4JDeFOqUsScTsPNhF89U7XCb7Tp6UWvv2SAq22V2NfQW17DZUC8MNXD-zmIXV2AhZaBBNibJWcFfOD8wZQOb0oUyLzJwtOtW-owPLIeFdpuOC4w";
+    use crate::utils::mock_server::{
+        MockResponse, MockServer, MOCK_MOBILE_PHONE, QUERY_MOBILE_PHONE,
+    };
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let auth_token = "test-token";
     let user_agent = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/132.0.0.0 Safari/537.36 Edg/132.0.0.0";
-    let response = query_mobile_phone(&auth_token, &user_agent).await;
-    println!("{:?}", response.unwrap());
+
+    let response = fetch_mobile_phone(&ctx, auth_token, user_agent).await;
+    assert_eq!(response.unwrap(), MOCK_MOBILE_PHONE);
+
+    // token过期
+    server.respond(QUERY_MOBILE_PHONE, vec![MockResponse::expired_token()]);
+    let response = fetch_mobile_phone(&ctx, auth_token, user_agent).await;
+    assert!(matches!(response, Err(Error::TokenExpired)));
+
+    // 按业务码分类，只有登录失效的业务码才视为token失效
+    server.respond(
+        QUERY_MOBILE_PHONE,
+        vec![MockResponse::business("1001", "请先登录")],
+    );
+    let response = fetch_mobile_phone(&ctx, auth_token, user_agent).await;
+    assert!(matches!(response, Err(Error::TokenExpired)));
+    server.respond(
+        QUERY_MOBILE_PHONE,
+        vec![MockResponse::business("9999", "系统异常")],
+    );
+    let response = fetch_mobile_phone(&ctx, auth_token, user_agent).await;
+    assert!(matches!(response, Err(Error::Business { .. })));
+    server.respond(QUERY_MOBILE_PHONE, vec![MockResponse::not_json()]);
+    let response = fetch_mobile_phone(&ctx, auth_token, user_agent).await;
+    assert!(matches!(response, Err(Error::InvalidResponse(_))));
 }
@@ -1,118 +1,384 @@
-use crate::api::ApiResponse;
-use crate::utils::request::{self, Data, Headers};
+use crate::api::code::{Outcome, UnknownCodes};
+use crate::api::strategy::{ReceiveMode, ReceiveStrategy};
+use crate::api::{parse_response, ApiResponse};
+use crate::error::{Error, Result};
+use crate::utils::request::{Data, Headers, RequestContext};
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::task::JoinSet;
 use tokio::time::{Duration, Instant};
 
-// 添加静态变量来追踪 zoneId
-static ZONE_ID_COUNTER: AtomicU32 = AtomicU32::new(1);
+// 领券时使用的 zoneId
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ZoneSelection {
+    Pinned(i64),     // 固定使用一个 zoneId
+    Cycle(Vec<i64>), // 依次轮换列表中的 zoneId
+}
 
-pub async fn fetch_receive(
-    auth_token: String,
+impl ZoneSelection {
+    pub fn zone_ids(&self) -> Vec<i64> {
+        match self {
+            ZoneSelection::Pinned(id) => vec![*id],
+            ZoneSelection::Cycle(ids) => ids.clone(),
+        }
+    }
+}
+
+// 单个领券任务的 zoneId 游标，同一任务的并发请求共享
+struct ZoneCursor {
+    zone_ids: Vec<i64>,
+    next: AtomicUsize,
+}
+
+impl ZoneCursor {
+    fn new(selection: &ZoneSelection) -> Self {
+        Self {
+            zone_ids: selection.zone_ids(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    fn next(&self) -> i64 {
+        let index = self.next.fetch_add(1, Ordering::Relaxed);
+        self.zone_ids[index % self.zone_ids.len()]
+    }
+}
+
+// 被限流时的退避间隔范围
+const MIN_BACKOFF: Duration = Duration::from_millis(200);
+const MAX_BACKOFF: Duration = Duration::from_millis(1600);
+
+// 单个优惠券的领取请求
+#[derive(Clone)]
+struct ReceiveTask {
+    ctx: RequestContext,
+    headers: Arc<Headers>, // 领券账号的请求头，任务开始时绑定
     red_pack_task_id: String,
     community_id: String,
-) -> Result<String, ReceiveError> {
-    let start_time = Instant::now();
-    let timeout_duration = Duration::from_secs(5);
-
-    // 减少并发数量到2个
-    let mut handles = vec![];
-    for _ in 0..2 {
-        let auth_token = auth_token.clone();
-        let red_pack_task_id = red_pack_task_id.clone();
-        let community_id = community_id.clone();
-
-        let handle = tokio::spawn(async move {
-            let mut last_error = None;
-            
-            while Instant::now().duration_since(start_time) < timeout_duration {
-                let current_zone_id = ZONE_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
-
-                let data = Data {
-                    communityId: community_id.clone(),
-                    redPackTaskId: red_pack_task_id.clone(),
-                    zoneId: current_zone_id.to_string(),
-                };
-
-                let headers = Headers {
-                    auth_token: auth_token.clone(),
-                    user_agent: crate::api::queryMobilePhone::read_saved_user_agent(),
-                };
-
-                // 增加单个请求超时时间
-                match tokio::time::timeout(
-                    Duration::from_secs(2), // 增加到2秒
-                    request::request(
-                        PathBuf::from("community/coupon/center/receive"),
-                        Some(data),
-                        Some(headers),
-                    ),
-                )
-                .await
-                {
-                    Ok(Ok(response)) => {
-                        let json: ApiResponse = match serde_json::from_str(&response) {
-                            Ok(json) => json,
-                            Err(_) => {
-                                last_error = Some(ReceiveError("响应格式错误".to_string()));
-                                continue;
-                            }
-                        };
-                        
-                        if json.success {
-                            return Ok("领取成功".to_string());
-                        } else if json.responseCode == "2040" {
-                            return Err(ReceiveError(json.responseMsg));
-                        }
-                        last_error = Some(ReceiveError(json.responseMsg));
-                    }
-                    Ok(Err(e)) => {
-                        last_error = Some(ReceiveError(e.to_string()));
-                    }
-                    Err(_) => {
-                        last_error = Some(ReceiveError("请求超时".to_string()));
-                    }
-                }
+    zones: Arc<ZoneCursor>,
+    request_timeout: Duration,
+    unknown_codes: UnknownCodes,
+}
+
+impl ReceiveTask {
+    // 发送一次领券请求
+    async fn attempt(&self) -> Result<String> {
+        let data = Data {
+            communityId: self.community_id.clone(),
+            redPackTaskId: self.red_pack_task_id.clone(),
+            zoneId: self.zones.next().to_string(),
+        };
+
+        let result = match tokio::time::timeout(
+            self.request_timeout,
+            self.ctx.request(
+                PathBuf::from("community/coupon/center/receive"),
+                Some(data),
+                Some(&*self.headers),
+            ),
+        )
+        .await
+        {
+            Ok(Ok(response)) => parse_response::<ApiResponse>(&response)
+                .and_then(ApiResponse::check)
+                .map(|_| "领取成功".to_string()),
+            Ok(Err(e)) => Err(e),
+            Err(_) => Err(Error::Timeout),
+        };
+        if let Err(Error::Business { code, msg }) = &result {
+            self.unknown_codes.record(code, msg);
+        }
+        result
+    }
 
-                // 增加请求间隔
-                tokio::time::sleep(Duration::from_millis(100)).await;
+    // 在截止时间前按间隔重试
+    async fn retry_until(&self, deadline: Instant, interval: Duration) -> Result<String> {
+        let mut last_error = None;
+        let mut delay = interval;
+
+        while Instant::now() < deadline {
+            match self.attempt().await {
+                Ok(msg) => return Ok(msg),
+                // 已抢完、token失效、风控等无需继续重试
+                Err(e) if !e.is_retryable() => return Err(e),
+                Err(e) => {
+                    // 被限流时指数退避，其余情况按固定间隔重试
+                    delay = if e.outcome() == Outcome::RetryLater {
+                        (delay * 2).clamp(MIN_BACKOFF, MAX_BACKOFF)
+                    } else {
+                        interval
+                    };
+                    last_error = Some(e);
+                }
             }
 
-            Err(last_error.unwrap_or_else(|| ReceiveError("请求超时".to_string())))
-        });
+            tokio::time::sleep_until((Instant::now() + delay).min(deadline)).await;
+        }
 
-        handles.push(handle);
-        
-        // 增加任务创建间隔
-        tokio::time::sleep(Duration::from_millis(50)).await;
+        Err(last_error.unwrap_or(Error::Timeout))
     }
+}
 
-    let mut last_error = None;
-    for handle in handles {
-        match handle.await.unwrap() {
+pub async fn fetch_receive(
+    ctx: &RequestContext,
+    headers: Headers,
+    red_pack_task_id: String,
+    community_id: String,
+    zones: &ZoneSelection,
+    strategy: &ReceiveStrategy,
+    unknown_codes: &UnknownCodes,
+) -> Result<String> {
+    if zones.zone_ids().is_empty() {
+        return Err(Error::Config("没有可用的 zoneId".to_string()));
+    }
+    let deadline = Instant::now() + strategy.window();
+    let task = ReceiveTask {
+        ctx: ctx.clone(),
+        headers: Arc::new(headers),
+        red_pack_task_id,
+        community_id,
+        zones: Arc::new(ZoneCursor::new(zones)),
+        request_timeout: strategy.request_timeout(),
+        unknown_codes: unknown_codes.clone(),
+    };
+
+    // 爆发模式：在T0同时发出多个请求
+    if strategy.mode == ReceiveMode::Burst {
+        let mut burst = JoinSet::new();
+        for _ in 0..strategy.burst_count.max(1) {
+            let task = task.clone();
+            burst.spawn(async move { task.attempt().await });
+        }
+        match first_result(&mut burst).await {
             Ok(msg) => return Ok(msg),
-            Err(e) => last_error = Some(e),
+            Err(e) if !e.is_retryable() => return Err(e),
+            Err(_) => {}
         }
     }
 
-    Err(last_error.unwrap_or_else(|| ReceiveError("所有请求均失败".to_string())))
-}
-
-#[derive(Debug)]
-pub struct ReceiveError(pub String);
+    // 多个任务错开启动，在窗口内持续重试
+    let mut workers = JoinSet::new();
+    for i in 0..strategy.workers.max(1) {
+        if i > 0 {
+            tokio::time::sleep(strategy.stagger()).await;
+        }
+        let task = task.clone();
+        let interval = strategy.interval();
+        workers.spawn(async move { task.retry_until(deadline, interval).await });
+    }
 
-unsafe impl Send for ReceiveError {}
-unsafe impl Sync for ReceiveError {}
+    first_result(&mut workers).await
+}
 
-impl std::error::Error for ReceiveError {}
-impl std::fmt::Display for ReceiveError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.0)
+// 等待第一个成功或不可重试的结果，剩余任务随 JoinSet 一起取消
+async fn first_result(set: &mut JoinSet<Result<String>>) -> Result<String> {
+    let mut last_error = None;
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(Ok(msg)) => return Ok(msg),
+            Ok(Err(e)) if !e.is_retryable() => return Err(e),
+            Ok(Err(e)) => last_error = Some(e),
+            Err(_) => {}
+        }
     }
+
+    Err(last_error.unwrap_or(Error::Timeout))
 }
 
 #[tokio::test]
 async fn test_fetch_info() {
-    let response = fetch_receive("123".to_string(), "123".to_string(), "14".to_string()).await;
-    println!("{:#?}", response);
+    use crate::utils::mock_server::{MockResponse, MockServer, COUPON_RECEIVE};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let zones = ZoneSelection::Pinned(4);
+    let strategy = ReceiveStrategy {
+        window_ms: 1000,
+        request_timeout_ms: 200,
+        ..Default::default()
+    };
+
+    // 重试后领取成功
+    server.respond(
+        COUPON_RECEIVE,
+        vec![
+            MockResponse::not_json(),
+            MockResponse::business("9999", "活动未开始"),
+            MockResponse::success(serde_json::Value::Null),
+        ],
+    );
+    let response = fetch_receive(
+        &ctx,
+        Headers::new("123", "test-agent"),
+        "123".to_string(),
+        "14".to_string(),
+        &zones,
+        &strategy,
+        &UnknownCodes::default(),
+    )
+    .await;
+    assert_eq!(response.unwrap(), "领取成功");
+    let request = &server.requests_to(COUPON_RECEIVE)[0];
+    assert_eq!(request.json().unwrap()["communityId"], "14");
+    assert_eq!(request.json().unwrap()["redPackTaskId"], "123");
+    assert_eq!(request.json().unwrap()["zoneId"], "4");
+
+    // 2040 立即结束
+    server.respond(COUPON_RECEIVE, vec![MockResponse::already_received()]);
+    let response = fetch_receive(
+        &ctx,
+        Headers::new("123", "test-agent"),
+        "123".to_string(),
+        "14".to_string(),
+        &zones,
+        &strategy,
+        &UnknownCodes::default(),
+    )
+    .await;
+    assert!(matches!(response, Err(Error::AlreadyReceived(msg)) if msg == "优惠券已抢完"));
+
+    // 其他不可重试的业务码立即结束
+    server.respond(
+        COUPON_RECEIVE,
+        vec![MockResponse::business("2041", "已达到领取上限")],
+    );
+    let response = fetch_receive(
+        &ctx,
+        Headers::new("123", "test-agent"),
+        "123".to_string(),
+        "14".to_string(),
+        &zones,
+        &strategy,
+        &UnknownCodes::default(),
+    )
+    .await;
+    assert!(matches!(response, Err(Error::Rejected { code, .. }) if code == "2041"));
+
+    // token失效同样立即结束
+    server.respond(COUPON_RECEIVE, vec![MockResponse::expired_token()]);
+    let response = fetch_receive(
+        &ctx,
+        Headers::new("123", "test-agent"),
+        "123".to_string(),
+        "14".to_string(),
+        &zones,
+        &strategy,
+        &UnknownCodes::default(),
+    )
+    .await;
+    assert!(matches!(response, Err(Error::TokenExpired)));
+}
+
+#[tokio::test]
+async fn test_fetch_receive_strategy() {
+    use crate::utils::mock_server::{MockResponse, MockServer, COUPON_RECEIVE};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let zones = ZoneSelection::Pinned(1);
+    let receive = |strategy: ReceiveStrategy| {
+        let ctx = ctx.clone();
+        let zones = zones.clone();
+        async move {
+            fetch_receive(
+                &ctx,
+                Headers::new("123", "test-agent"),
+                "123".to_string(),
+                "14".to_string(),
+                &zones,
+                &strategy,
+                &UnknownCodes::default(),
+            )
+            .await
+        }
+    };
+
+    // 爆发模式在T0同时发出N个请求
+    let strategy = ReceiveStrategy {
+        mode: ReceiveMode::Burst,
+        burst_count: 5,
+        ..Default::default()
+    };
+    assert_eq!(receive(strategy).await.unwrap(), "领取成功");
+    assert_eq!(server.requests_to(COUPON_RECEIVE).len(), 5);
+
+    // 慢响应按单次超时处理，窗口结束后返回超时
+    server.respond(
+        COUPON_RECEIVE,
+        vec![MockResponse::slow(
+            Duration::from_millis(500),
+            MockResponse::success(serde_json::Value::Null),
+        )],
+    );
+    let strategy = ReceiveStrategy {
+        workers: 1,
+        window_ms: 300,
+        request_timeout_ms: 100,
+        ..Default::default()
+    };
+    assert!(matches!(receive(strategy).await, Err(Error::Timeout)));
+
+    // 被限流时退避，请求数远少于固定间隔
+    server.respond(
+        COUPON_RECEIVE,
+        vec![MockResponse::business("9001", "操作太频繁")],
+    );
+    let before = server.requests_to(COUPON_RECEIVE).len();
+    let strategy = ReceiveStrategy {
+        workers: 1,
+        window_ms: 1000,
+        interval_ms: 10,
+        ..Default::default()
+    };
+    assert!(matches!(
+        receive(strategy).await,
+        Err(Error::RateLimited(_))
+    ));
+    let sent = server.requests_to(COUPON_RECEIVE).len() - before;
+    assert!(sent <= 5, "限流时发送了 {} 个请求", sent);
+}
+
+#[tokio::test]
+async fn test_fetch_receive_zone_cycle() {
+    use crate::utils::mock_server::{MockResponse, MockServer, COUPON_RECEIVE};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    server.respond(
+        COUPON_RECEIVE,
+        vec![
+            MockResponse::business("2030", "活动未开始"),
+            MockResponse::business("2030", "活动未开始"),
+            MockResponse::business("2030", "活动未开始"),
+            MockResponse::success(serde_json::Value::Null),
+        ],
+    );
+
+    // 每个任务独立地按列表轮换 zoneId
+    let zones = ZoneSelection::Cycle(vec![3, 4]);
+    let strategy = ReceiveStrategy {
+        workers: 1,
+        interval_ms: 0,
+        ..Default::default()
+    };
+    let response = fetch_receive(
+        &ctx,
+        Headers::new("123", "test-agent"),
+        "123".to_string(),
+        "14".to_string(),
+        &zones,
+        &strategy,
+        &UnknownCodes::default(),
+    )
+    .await;
+    assert_eq!(response.unwrap(), "领取成功");
+
+    let zone_ids: Vec<String> = server
+        .requests_to(COUPON_RECEIVE)
+        .iter()
+        .map(|r| r.json().unwrap()["zoneId"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(zone_ids, vec!["3", "4", "3", "4"]);
 }
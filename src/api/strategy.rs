@@ -0,0 +1,92 @@
+use crate::config::{self, Section};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// 领券请求的发送方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReceiveMode {
+    Steady, // 稳定：多个任务错开启动，在整个窗口内按间隔重试
+    Burst,  // 爆发：在T0同时发出N个请求，未成功再按稳定方式重试
+}
+
+// 领券策略，每次活动可以单独调整
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReceiveStrategy {
+    pub mode: ReceiveMode,
+    pub workers: usize,          // 并发任务数
+    pub burst_count: usize,      // 爆发模式下T0同时发出的请求数
+    pub window_ms: u64,          // 整体抢票窗口
+    pub request_timeout_ms: u64, // 单次请求超时
+    pub interval_ms: u64,        // 每个任务两次请求之间的间隔
+    pub stagger_ms: u64,         // 任务之间的启动间隔
+    pub lead_ms: u64,            // 比开抢时间提前发出请求的时长
+    pub warmup_ms: u64,          // 开抢前多久预热连接、校验账号
+    pub warm_connections: usize, // 预热的连接数
+}
+
+impl Default for ReceiveStrategy {
+    fn default() -> Self {
+        Self {
+            mode: ReceiveMode::Steady,
+            workers: 2,
+            burst_count: 4,
+            window_ms: 5000,
+            request_timeout_ms: 2000,
+            interval_ms: 100,
+            stagger_ms: 50,
+            lead_ms: 0,
+            warmup_ms: 3000,
+            warm_connections: 4,
+        }
+    }
+}
+
+impl ReceiveStrategy {
+    pub fn window(&self) -> Duration {
+        Duration::from_millis(self.window_ms)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_millis(self.request_timeout_ms)
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_millis(self.interval_ms)
+    }
+
+    pub fn stagger(&self) -> Duration {
+        Duration::from_millis(self.stagger_ms)
+    }
+
+    pub fn lead(&self) -> Duration {
+        Duration::from_millis(self.lead_ms)
+    }
+
+    pub fn warmup(&self) -> Duration {
+        Duration::from_millis(self.warmup_ms)
+    }
+
+    // 从配置文件读取策略，没有保存过时使用默认值，格式错误时返回出错的字段
+    pub fn load() -> Result<Self> {
+        config::load(Section::Strategy)
+    }
+
+    // 读取、修改并保存策略，期间一直持有锁
+    pub fn update<R>(change: impl FnOnce(&mut Self) -> Result<R>) -> Result<R> {
+        config::update(Section::Strategy, change)
+    }
+}
+
+#[test]
+fn test_strategy_defaults() {
+    // 缺失的字段使用默认值
+    let strategy: ReceiveStrategy =
+        serde_json::from_str(r#"{ "mode": "Burst", "burst_count": 8 }"#).unwrap();
+    assert_eq!(strategy.mode, ReceiveMode::Burst);
+    assert_eq!(strategy.burst_count, 8);
+    assert_eq!(strategy.workers, 2);
+    assert_eq!(strategy.window(), Duration::from_secs(5));
+    assert_eq!(strategy.lead(), Duration::ZERO);
+}
@@ -0,0 +1,102 @@
+use crate::api::queryMobilePhone::fetch_account_phone;
+use crate::error::Result;
+use crate::utils::request::{Headers, RequestContext};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+// 开抢前预热连接并校验账号
+//
+// 对每个账号调用一次 queryMobilePhone 校验 token，连接数不够时再轮流补发，
+// 这些并发请求建立的连接会留在连接池中，T0 的领券请求可以直接复用。
+// 返回结果与 accounts 一一对应，成功时为手机号，到 deadline 还没有结果的账号为 None，
+// 未完成的请求会被取消，不会拖延开抢。
+pub async fn prewarm(
+    ctx: &RequestContext,
+    accounts: &[Headers],
+    connections: usize,
+    deadline: Instant,
+) -> Vec<Option<Result<String>>> {
+    if accounts.is_empty() {
+        return Vec::new();
+    }
+
+    // 连接数不超过连接池能保留的空闲连接数
+    let connections = connections
+        .max(accounts.len())
+        .min(ctx.config().pool_max_idle_per_host.max(accounts.len()));
+
+    let mut set = JoinSet::new();
+    for i in 0..connections {
+        let ctx = ctx.clone();
+        let account = accounts[i % accounts.len()].clone();
+        set.spawn(async move { (i, fetch_account_phone(&ctx, &account).await) });
+    }
+
+    let mut results: Vec<Option<Result<String>>> = accounts.iter().map(|_| None).collect();
+    while let Ok(Some(joined)) = tokio::time::timeout_at(deadline, set.join_next()).await {
+        // 每个账号以第一次请求的结果为准，补发的请求只用于建立连接
+        if let Ok((i, result)) = joined {
+            if i < accounts.len() {
+                results[i] = Some(result);
+            }
+        }
+    }
+    results
+}
+
+#[tokio::test]
+async fn test_prewarm() {
+    use crate::error::Error;
+    use crate::utils::mock_server::{
+        MockResponse, MockServer, MOCK_MOBILE_PHONE, QUERY_MOBILE_PHONE,
+    };
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let accounts = vec![
+        Headers::new("token-a", "ua-a"),
+        Headers::new("token-b", "ua-b"),
+    ];
+
+    let deadline = || Instant::now() + std::time::Duration::from_secs(5);
+
+    // 每个账号至少请求一次，补足到指定的连接数
+    let results = prewarm(&ctx, &accounts, 4, deadline()).await;
+    assert_eq!(results.len(), 2);
+    assert!(results
+        .iter()
+        .all(|r| matches!(r, Some(Ok(phone)) if phone == MOCK_MOBILE_PHONE)));
+    let requests = server.requests_to(QUERY_MOBILE_PHONE);
+    assert_eq!(requests.len(), 4);
+    assert_eq!(
+        requests
+            .iter()
+            .filter(|r| r.header("authToken") == Some("token-b"))
+            .count(),
+        2
+    );
+
+    // token失效的账号在开抢前就能发现
+    server.respond(QUERY_MOBILE_PHONE, vec![MockResponse::expired_token()]);
+    let results = prewarm(&ctx, &accounts[..1], 1, deadline()).await;
+    assert!(matches!(results[0], Some(Err(Error::TokenExpired))));
+
+    // 到截止时间还没有响应的账号没有结果，不等待请求超时
+    server.respond(
+        QUERY_MOBILE_PHONE,
+        vec![MockResponse::slow(
+            std::time::Duration::from_secs(2),
+            MockResponse::success(serde_json::json!(MOCK_MOBILE_PHONE)),
+        )],
+    );
+    let start = Instant::now();
+    let results = prewarm(
+        &ctx,
+        &accounts[..1],
+        1,
+        start + std::time::Duration::from_millis(200),
+    )
+    .await;
+    assert!(results[0].is_none());
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+}
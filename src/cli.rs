@@ -0,0 +1,764 @@
+// 命令行模式：不启动界面，直接完成登录、账号、游戏、抢票和定时任务管理
+//
+// 每个命令默认输出便于阅读的文字，加 --json 时输出 JSON，方便脚本和 systemd 定时器调用。
+
+use crate::daemon;
+use chrono::{Datelike, Local, NaiveDateTime};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{bail, eyre};
+use color_eyre::Result;
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use zeroize::Zeroizing;
+use UTermux::api::info::get_info;
+use UTermux::config;
+use UTermux::engine::accounts;
+use UTermux::engine::events::Events;
+use UTermux::engine::games::{self, DEFAULT_GAMES, ZONES};
+use UTermux::engine::grab;
+use UTermux::engine::jobs::{Job, JobList, JobStatus, Repeat};
+use UTermux::engine::transfer::{self, Format};
+use UTermux::utils::persist;
+use UTermux::utils::request::{RequestConfig, RequestContext};
+use UTermux::utils::scheduler::Countdowns;
+
+// 不指定开抢时间时，留出校准服务器时间的余量后立即抢票
+const GRAB_NOW_DELAY: Duration = Duration::from_secs(1);
+// 账号文件口令的环境变量，设置后不再交互输入，便于后台模式和脚本使用
+pub const PASSPHRASE_ENV: &str = "UTERMUX_PASSPHRASE";
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "utermux",
+    version,
+    about = "U号租抢票助手，不带子命令时启动界面"
+)]
+pub struct Cli {
+    #[arg(long, global = true, help = "以 JSON 格式输出")]
+    pub json: bool,
+    #[arg(long, help = "无界面运行定时任务，同 run 子命令")]
+    pub daemon: bool,
+    #[arg(long, requires = "daemon", help = "后台模式的日志文件")]
+    pub log: Option<PathBuf>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    #[command(about = "使用 auth_token 登录并保存账号")]
+    Login {
+        #[arg(long, help = "账号的 auth_token")]
+        token: String,
+        #[arg(long, default_value = "", help = "请求使用的 UserAgent")]
+        ua: String,
+    },
+    #[command(about = "管理已保存的账号")]
+    Accounts {
+        #[command(subcommand)]
+        action: AccountsAction,
+    },
+    #[command(about = "查看、启用或停用游戏")]
+    Games {
+        #[command(subcommand)]
+        action: GamesAction,
+    },
+    #[command(about = "查看领券中心的专区和优惠券")]
+    Info {
+        #[arg(long, default_value = "14", help = "游戏的 communityId")]
+        community: String,
+        #[arg(long, help = "使用的账号手机号，默认使用当前激活的账号")]
+        account: Option<String>,
+    },
+    #[command(about = "在前台等待到开抢时间后抢票，不指定时间时立即开抢")]
+    Grab(JobArgs),
+    #[command(about = "管理定时任务")]
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
+    },
+    #[command(about = "无界面运行定时任务")]
+    Run {
+        #[arg(long, help = "日志文件，默认写入配置目录下的 daemon.log")]
+        log: Option<PathBuf>,
+    },
+    #[command(about = "检查配置文件")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    #[command(about = "检查配置文件的格式和内容，指出有问题的字段")]
+    Check,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AccountsAction {
+    #[command(about = "列出已保存的账号")]
+    List,
+    #[command(about = "激活账号")]
+    Activate {
+        mobile_phone: String,
+        #[arg(long, help = "同时停用其他账号")]
+        only: bool,
+    },
+    #[command(about = "停用账号")]
+    Deactivate { mobile_phone: String },
+    #[command(about = "删除账号")]
+    Remove { mobile_phone: String },
+    #[command(about = "设置账号备注，不填备注时清除")]
+    Rename {
+        mobile_phone: String,
+        alias: Option<String>,
+    },
+    #[command(about = "用新的token替换账号的token")]
+    Token {
+        mobile_phone: String,
+        #[arg(long, help = "新的 authToken")]
+        token: String,
+        #[arg(long, default_value = "", help = "新的 User-Agent，默认沿用原来的")]
+        ua: String,
+    },
+    #[command(about = "调整账号顺序")]
+    Move {
+        mobile_phone: String,
+        #[arg(value_enum)]
+        direction: Direction,
+    },
+    #[command(about = "校验所有账号的token是否有效")]
+    Check,
+    #[command(about = "用口令加密明文保存的账号文件")]
+    Encrypt,
+    #[command(about = "从 CSV、JSON 或 AuthToken:... MobilePhone:... 文本文件批量导入账号")]
+    Import {
+        file: PathBuf,
+        #[arg(long, value_enum, help = "文件格式，默认按扩展名判断")]
+        format: Option<FileFormat>,
+        #[arg(
+            long,
+            default_value = "",
+            help = "文件中没有 User-Agent 的账号使用的 User-Agent"
+        )]
+        ua: String,
+    },
+    #[command(about = "导出账号，不指定手机号时导出全部")]
+    Export {
+        mobile_phones: Vec<String>,
+        #[arg(short, long, help = "写入文件，默认输出到终端")]
+        output: Option<PathBuf>,
+        #[arg(
+            long,
+            value_enum,
+            help = "文件格式，默认按扩展名判断，输出到终端时为 json"
+        )]
+        format: Option<FileFormat>,
+        #[arg(long, help = "隐藏token，导出的文件不能再导入")]
+        redact: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum FileFormat {
+    Csv,
+    Json,
+    Text,
+}
+
+impl From<FileFormat> for Format {
+    fn from(format: FileFormat) -> Self {
+        match format {
+            FileFormat::Csv => Format::Csv,
+            FileFormat::Json => Format::Json,
+            FileFormat::Text => Format::Text,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Up,
+    Down,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GamesAction {
+    #[command(about = "列出游戏及已获取的优惠券")]
+    List,
+    #[command(about = "启用游戏并获取它的优惠券")]
+    Enable { name: String },
+    #[command(about = "停用游戏")]
+    Disable { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum JobsAction {
+    #[command(about = "添加定时任务")]
+    Add {
+        #[command(flatten)]
+        job: JobArgs,
+        #[arg(long, value_enum, default_value = "once", help = "重复方式")]
+        repeat: RepeatKind,
+        #[arg(
+            long,
+            conflicts_with = "repeat",
+            help = "cron 表达式（秒 分 时 日 月 周），按表达式计算开抢时间"
+        )]
+        cron: Option<String>,
+    },
+    #[command(about = "列出定时任务")]
+    List,
+    #[command(about = "删除定时任务")]
+    Rm { id: u64 },
+}
+
+// 抢票和定时任务共用的参数
+#[derive(Args, Debug)]
+pub struct JobArgs {
+    #[arg(long = "game", help = "游戏名称，可重复，默认使用设置中启用的游戏")]
+    games: Vec<String>,
+    #[arg(long = "zone", required = true, help = "专区名称，可重复")]
+    zones: Vec<String>,
+    #[arg(long, value_parser = parse_time, help = "开抢时间，如 \"2026-10-19 10:00:00\"")]
+    at: Option<NaiveDateTime>,
+    #[arg(long = "account", help = "账号手机号，默认使用当前激活的账号")]
+    accounts: Vec<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum RepeatKind {
+    Once,
+    Daily,
+    Weekly,
+}
+
+// 解析开抢时间，秒可以省略
+fn parse_time(s: &str) -> std::result::Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M"))
+        .map_err(|_| format!("时间格式应为 \"2026-10-19 10:00:00\"：{}", s))
+}
+
+// 按输出格式打印结果
+fn output<T: Serialize>(json: bool, value: &T, human: impl FnOnce()) -> Result<()> {
+    if json {
+        println!("{}", serde_json::to_string(value)?);
+    } else {
+        human();
+    }
+    Ok(())
+}
+
+// 命令失败时只输出一行错误，便于脚本处理
+pub fn report_error(error: &color_eyre::Report, json: bool) {
+    if json {
+        println!("{}", json!({ "error": error.to_string() }));
+    } else {
+        eprintln!("错误: {}", error);
+    }
+}
+
+// 打印引擎发出的事件
+fn spawn_printer(json: bool) -> (Events, JoinHandle<()>) {
+    let (events, mut receiver) = Events::channel(100);
+    let printer = tokio::spawn(async move {
+        while let Some(event) = receiver.recv().await {
+            let _ = output(json, &json!({ "event": event }), || println!("{}", event));
+        }
+    });
+    (events, printer)
+}
+
+fn env_passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+// 读取口令，优先使用环境变量，口令在释放时清零
+fn read_passphrase(prompt: &str) -> Result<Zeroizing<String>> {
+    let passphrase = match env_passphrase() {
+        Some(passphrase) => passphrase,
+        None => rpassword::prompt_password(prompt).map_err(|e| {
+            eyre!(
+                "无法读取口令（{}），请在终端中运行或设置 {}",
+                e,
+                PASSPHRASE_ENV
+            )
+        })?,
+    };
+    Ok(Zeroizing::new(passphrase))
+}
+
+// 账号文件已加密时输入口令解锁
+pub fn unlock_accounts() -> Result<()> {
+    if accounts::is_locked() {
+        let passphrase = read_passphrase("请输入账号文件的口令: ")?;
+        accounts::unlock(&passphrase)?;
+    }
+    Ok(())
+}
+
+// 是否读写账号的token，只有这些命令需要先解锁账号文件
+fn uses_accounts(command: &Command) -> bool {
+    match command {
+        Command::Login { .. }
+        | Command::Accounts { .. }
+        | Command::Info { .. }
+        | Command::Grab(_)
+        | Command::Run { .. } => true,
+        Command::Games { action } => matches!(action, GamesAction::Enable { .. }),
+        // 指定账号时要确认账号存在
+        Command::Jobs { action } => {
+            matches!(action, JobsAction::Add { job, .. } if !job.accounts.is_empty())
+        }
+        Command::Config { .. } => false,
+    }
+}
+
+pub async fn execute(command: Command, json: bool) -> Result<()> {
+    let request_context = RequestContext::new(RequestConfig::from_env())?;
+    if uses_accounts(&command) {
+        unlock_accounts()?;
+    }
+    match command {
+        Command::Login { token, ua } => login(&request_context, &token, &ua, json).await,
+        Command::Accounts { action } => accounts(&request_context, action, json).await,
+        Command::Games { action } => games(&request_context, action, json).await,
+        Command::Info { community, account } => {
+            info(&request_context, &community, account.as_deref(), json).await
+        }
+        Command::Grab(args) => grab(request_context, args, json).await,
+        Command::Jobs { action } => jobs(action, json),
+        Command::Run { log } => daemon::run(log).await,
+        Command::Config { action } => config(action, json),
+    }
+}
+
+fn config(action: ConfigAction, json: bool) -> Result<()> {
+    match action {
+        ConfigAction::Check => {
+            let problems = config::check()?;
+            let path = config::config_path().unwrap_or_default();
+            let report = json!({
+                "path": path,
+                "schema_version": config::SCHEMA_VERSION,
+                "problems": problems,
+            });
+            output(json, &report, || {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+            })?;
+            if !problems.is_empty() {
+                bail!("配置文件 {} 有{}个问题", path.display(), problems.len());
+            }
+            if !json {
+                println!("配置文件 {} 没有问题", path.display());
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn login(request_context: &RequestContext, token: &str, ua: &str, json: bool) -> Result<()> {
+    let mobile_phone = accounts::login(request_context, token, ua).await?;
+    output(json, &json!({ "mobile_phone": mobile_phone }), || {
+        println!("登录成功!您的手机号是:{}", mobile_phone)
+    })
+}
+
+async fn accounts(
+    request_context: &RequestContext,
+    action: AccountsAction,
+    json: bool,
+) -> Result<()> {
+    let records = match action {
+        AccountsAction::List => accounts::list()?,
+        AccountsAction::Activate { mobile_phone, only } => accounts::activate(&mobile_phone, only)?,
+        AccountsAction::Deactivate { mobile_phone } => accounts::deactivate(&mobile_phone)?,
+        AccountsAction::Remove { mobile_phone } => accounts::remove(&mobile_phone)?,
+        AccountsAction::Rename {
+            mobile_phone,
+            alias,
+        } => accounts::rename(&mobile_phone, alias.as_deref())?,
+        AccountsAction::Token {
+            mobile_phone,
+            token,
+            ua,
+        } => accounts::replace_token(request_context, &mobile_phone, &token, &ua).await?,
+        AccountsAction::Move {
+            mobile_phone,
+            direction,
+        } => {
+            let offset = if direction == Direction::Up { -1 } else { 1 };
+            accounts::move_by(&mobile_phone, offset)?
+        }
+        AccountsAction::Check => {
+            let (events, printer) = spawn_printer(json);
+            let records = accounts::validate(request_context, &events).await;
+            drop(events);
+            let _ = printer.await;
+            records?
+        }
+        AccountsAction::Encrypt => {
+            let passphrase = read_passphrase("设置账号文件的口令: ")?;
+            // 交互输入时再确认一次
+            if env_passphrase().is_none()
+                && *Zeroizing::new(rpassword::prompt_password("再次输入口令: ")?) != *passphrase
+            {
+                bail!("两次输入的口令不一致");
+            }
+            let records = accounts::encrypt(&passphrase)?;
+            if !json {
+                println!("账号文件已加密，之后启动时需要输入口令");
+            }
+            records
+        }
+        AccountsAction::Import { file, format, ua } => {
+            let content = std::fs::read_to_string(&file)
+                .map_err(|e| eyre!("无法读取 {}: {}", file.display(), e))?;
+            let format = format.map_or_else(|| Format::from_path(&file), Format::from);
+            let entries = transfer::parse(&content, format)?;
+            if entries.is_empty() {
+                bail!("{} 中没有找到账号", file.display());
+            }
+            let (events, printer) = spawn_printer(json);
+            let records = accounts::import(request_context, entries, &ua, &events).await;
+            drop(events);
+            let _ = printer.await;
+            records?
+        }
+        AccountsAction::Export {
+            mobile_phones,
+            output,
+            format,
+            redact,
+        } => {
+            let format = match (format, &output) {
+                (Some(format), _) => format.into(),
+                (None, Some(path)) => Format::from_path(path),
+                (None, None) => Format::Json,
+            };
+            let content = accounts::export(&mobile_phones, format, redact)?;
+            match output {
+                Some(path) => {
+                    persist::write_private(&path, content)
+                        .map_err(|e| eyre!("无法写入 {}: {}", path.display(), e))?;
+                    if !json {
+                        println!("已导出到 {}", path.display());
+                    }
+                }
+                None => print!("{}", content),
+            }
+            return Ok(());
+        }
+    };
+
+    // 不输出 auth_token
+    let accounts: Vec<_> = records
+        .iter()
+        .map(|r| {
+            json!({
+                "mobile_phone": r.mobile_phone,
+                "alias": r.alias,
+                "user_agent": r.user_agent,
+                "active": r.active,
+                "valid": r.valid,
+                "last_validated_at": r.last_validated_at,
+            })
+        })
+        .collect();
+    output(json, &accounts, || {
+        if records.is_empty() {
+            println!("还没有保存任何账号，请先登录");
+        }
+        for record in &records {
+            let mark = if record.active { "*" } else { " " };
+            println!("[{}] {} {}", mark, record.label(), record.status());
+        }
+    })
+}
+
+async fn games(request_context: &RequestContext, action: GamesAction, json: bool) -> Result<()> {
+    let mut configs = games::load()?;
+    let mut names = games::names(&configs);
+
+    let toggle = match action {
+        GamesAction::List => None,
+        GamesAction::Enable { name } => Some((name, true)),
+        GamesAction::Disable { name } => Some((name, false)),
+    };
+    if let Some((name, active)) = toggle {
+        if !names.contains(&name) && !DEFAULT_GAMES.contains(&name.as_str()) {
+            bail!("未知游戏 {}，可选：{}", name, names.join("、"));
+        }
+        let (events, printer) = spawn_printer(json);
+        configs = games::update_games(request_context, &[(name, active)], &events).await?;
+        drop(events);
+        let _ = printer.await;
+        names = games::names(&configs);
+    }
+
+    let list: Vec<_> = names
+        .iter()
+        .map(|name| {
+            let config = configs.get(name);
+            json!({
+                "name": name,
+                "active": config.map(|c| c.active).unwrap_or(false),
+                "community_id": config.map(|c| c.communityId.clone()),
+                "red_pack_tasks": config.map(|c| c.red_pack_tasks.clone()).unwrap_or_default(),
+            })
+        })
+        .collect();
+    output(json, &list, || {
+        for name in &names {
+            let Some(config) = configs.get(name) else {
+                println!("[ ] {}", name);
+                continue;
+            };
+            let mark = if config.active { "*" } else { " " };
+            let mut zones: Vec<String> = config
+                .red_pack_tasks
+                .iter()
+                .map(|(zone, tasks)| format!("{}:{}", zone, tasks.len()))
+                .collect();
+            zones.sort();
+            println!(
+                "[{}] {} (communityId {}) {}",
+                mark,
+                name,
+                config.communityId,
+                zones.join(" ")
+            );
+        }
+    })
+}
+
+async fn info(
+    request_context: &RequestContext,
+    community: &str,
+    account: Option<&str>,
+    json: bool,
+) -> Result<()> {
+    let accounts: Vec<String> = account.into_iter().map(String::from).collect();
+    let account =
+        accounts::resolve(&accounts)?.ok_or_else(|| eyre!("找不到可用的账号，请先登录"))?;
+    let center = get_info(request_context, account.auth_token, community.to_string()).await?;
+    output(json, &center, || {
+        for zone in &center.zones {
+            match zone.id {
+                Some(id) => println!("{} (zoneId {})", zone.name, id),
+                None => println!("{} (未返回zoneId)", zone.name),
+            }
+            for task in &zone.tasks {
+                let field = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+                println!(
+                    "  #{} 金额 {} 门槛 {} 库存 {} 限领 {} 开抢 {}",
+                    task.id,
+                    field(task.amount.map(|n| n.to_string())),
+                    field(task.threshold.map(|n| n.to_string())),
+                    field(task.stock.map(|n| n.to_string())),
+                    field(task.per_user_limit.map(|n| n.to_string())),
+                    field(task.start_time.clone()),
+                );
+            }
+        }
+    })
+}
+
+// 根据参数创建任务，校验专区和账号
+fn build_job(args: JobArgs, target: NaiveDateTime) -> Result<Job> {
+    if let Some(zone) = args
+        .zones
+        .iter()
+        .find(|zone| !ZONES.contains(&zone.as_str()))
+    {
+        bail!("未知专区 {}，可选：{}", zone, ZONES.join("、"));
+    }
+    for account in &args.accounts {
+        if accounts::resolve(std::slice::from_ref(account))?.is_none() {
+            bail!("找不到账号 {}", account);
+        }
+    }
+
+    let mut job = Job::new(target, args.zones);
+    job.games = args.games;
+    job.accounts = args.accounts;
+    Ok(job)
+}
+
+async fn grab(request_context: RequestContext, args: JobArgs, json: bool) -> Result<()> {
+    let now = Local::now().naive_local();
+    let target = match args.at {
+        Some(at) if at <= now => bail!("开抢时间 {} 已经过去", at),
+        Some(at) => at,
+        None => now + GRAB_NOW_DELAY,
+    };
+    let job = build_job(args, target)?;
+
+    // 抢票过程的事件逐行打印
+    let (events, printer) = spawn_printer(json);
+    let countdown = watch::Sender::new(Countdowns::new());
+    let status = grab::grab(&request_context, &job, &events, &countdown).await;
+    drop(events);
+    let _ = printer.await;
+
+    output(json, &json!({ "status": status }), || {
+        println!("抢票结束：{}", status.label())
+    })?;
+    match status {
+        JobStatus::Finished => Ok(()),
+        JobStatus::Failed(reason) => bail!("抢票失败：{}", reason),
+        status => bail!("抢票未完成：{}", status.label()),
+    }
+}
+
+fn jobs(action: JobsAction, json: bool) -> Result<()> {
+    match action {
+        JobsAction::Add { job, repeat, cron } => {
+            let now = Local::now().naive_local();
+            let target = match (job.at, &cron) {
+                (Some(at), _) => at,
+                // cron 任务的开抢时间由表达式决定
+                (None, Some(_)) => now,
+                (None, None) => bail!("请用 --at 指定开抢时间"),
+            };
+            let mut job = build_job(job, target)?;
+            let repeat = match (cron, repeat) {
+                (Some(expr), _) => Repeat::Cron(expr),
+                (None, RepeatKind::Once) => Repeat::Once,
+                (None, RepeatKind::Daily) => Repeat::Daily,
+                (None, RepeatKind::Weekly) => Repeat::Weekly(vec![target.weekday()]),
+            };
+            job.set_repeat(repeat, now)?;
+            if job.target <= now {
+                bail!("开抢时间 {} 已经过去", job.target);
+            }
+
+            let job = JobList::update(|list| {
+                let id = list.add(job);
+                Ok(list.get(id).cloned())
+            })?
+            .ok_or_else(|| eyre!("保存任务失败"))?;
+            output(json, &job, || println!("已添加定时任务 {}", job.describe()))
+        }
+        JobsAction::List => {
+            let list = JobList::load()?;
+            output(json, &list.jobs, || {
+                if list.jobs.is_empty() {
+                    println!("没有定时任务");
+                }
+                for job in &list.jobs {
+                    println!("{}", job.describe());
+                }
+            })
+        }
+        JobsAction::Rm { id } => {
+            let job = JobList::update(|list| Ok(list.remove(id)))?
+                .ok_or_else(|| eyre!("找不到定时任务#{}", id))?;
+            output(json, &job, || println!("已删除定时任务 {}", job.describe()))
+        }
+    }
+}
+
+#[test]
+fn test_cli_parse() {
+    let cli = Cli::try_parse_from([
+        "utermux",
+        "grab",
+        "--game",
+        "三国杀",
+        "--zone",
+        "每日专区",
+        "--at",
+        "2026-10-19 10:00",
+        "--json",
+    ])
+    .unwrap();
+    assert!(cli.json);
+    let Some(Command::Grab(args)) = cli.command else {
+        panic!("应解析为 grab 命令");
+    };
+    assert_eq!(args.games, vec!["三国杀"]);
+    assert_eq!(args.at, parse_time("2026-10-19 10:00:00").ok());
+
+    // 专区必填，cron 与 repeat 不能同时使用
+    assert!(Cli::try_parse_from(["utermux", "grab"]).is_err());
+    assert!(Cli::try_parse_from([
+        "utermux",
+        "jobs",
+        "add",
+        "--zone",
+        "每日专区",
+        "--repeat",
+        "daily",
+        "--cron",
+        "0 0 10 * * *",
+    ])
+    .is_err());
+    assert!(matches!(
+        Cli::try_parse_from(["utermux", "accounts", "move", "13800000000", "up"])
+            .unwrap()
+            .command,
+        Some(Command::Accounts {
+            action: AccountsAction::Move {
+                direction: Direction::Up,
+                ..
+            }
+        })
+    ));
+    assert!(
+        Cli::try_parse_from(["utermux", "--daemon", "--log", "/tmp/daemon.log"])
+            .unwrap()
+            .daemon
+    );
+}
+
+// 修改环境变量，只在 Linux 上按 XDG_CONFIG_HOME 使用临时的配置目录
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_jobs_add_unlocks_accounts() {
+    use UTermux::api::queryMobilePhone::save_token;
+    use UTermux::utils::vault;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("XDG_CONFIG_HOME", dir.path());
+    std::env::set_var(PASSPHRASE_ENV, "correct horse");
+    save_token("token-a", "13800000000", "ua").unwrap();
+    accounts::encrypt("correct horse").unwrap();
+    vault::set_unlocked(None);
+    assert!(accounts::is_locked());
+
+    let parse = |extra: &[&str]| {
+        let args = [
+            "utermux",
+            "jobs",
+            "add",
+            "--zone",
+            "每日专区",
+            "--at",
+            "2030-01-01 10:00",
+        ];
+        Cli::try_parse_from(args.iter().chain(extra))
+            .unwrap()
+            .command
+            .unwrap()
+    };
+    // 不指定账号时不需要口令
+    assert!(!uses_accounts(&parse(&[])));
+
+    // 指定账号时先用口令解锁，再确认账号存在
+    let command = parse(&["--account", "13800000000"]);
+    assert!(uses_accounts(&command));
+    execute(command, true).await.unwrap();
+    assert_eq!(JobList::load().unwrap().jobs[0].accounts, ["13800000000"]);
+    assert!(execute(parse(&["--account", "13900000000"]), true)
+        .await
+        .is_err());
+}
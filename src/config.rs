@@ -0,0 +1,615 @@
+// 统一的配置文件 config.json
+//
+// 账号、游戏、定时任务、抢票策略和界面设置按节保存在同一个文件中，写入一节时其他节保持不变。
+// 文件带有 schema_version，读取旧版本的文件时逐个版本升级。旧版本分散保存的
+// auth_token.json、AppGame.json 和 saved_date 第一次写入时合并进来，原文件改名为 *.migrated 保留。
+use crate::api::queryMobilePhone::TokenStorage;
+use crate::api::strategy::{ReceiveMode, ReceiveStrategy};
+use crate::engine::games::GameConfigs;
+use crate::engine::jobs::Job;
+use crate::error::{Error, Result};
+use crate::utils::persist::{self, FileLock};
+use crate::utils::vault::{self, Sealed};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 当前的配置格式版本
+pub const SCHEMA_VERSION: u32 = 1;
+pub const FILE_NAME: &str = "config.json";
+
+// 升级步骤，MIGRATIONS[i] 把版本 i 的文件升级到版本 i+1
+const MIGRATIONS: [fn(&mut Document); SCHEMA_VERSION as usize] = [
+    // 版本0是没有 schema_version 的文件，结构与版本1相同
+    |_| {},
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Accounts,
+    Games,
+    Jobs,
+    Strategy,
+    Ui,
+}
+
+impl Section {
+    pub const ALL: [Section; 5] = [
+        Section::Accounts,
+        Section::Games,
+        Section::Jobs,
+        Section::Strategy,
+        Section::Ui,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Accounts => "accounts",
+            Section::Games => "games",
+            Section::Jobs => "jobs",
+            Section::Strategy => "strategy",
+            Section::Ui => "ui",
+        }
+    }
+
+    // 旧版本保存这一节的文件，定时任务和抢票策略在旧版本中没有保存
+    fn legacy_file(self) -> Option<&'static str> {
+        match self {
+            Section::Accounts => Some("auth_token.json"),
+            Section::Games => Some("AppGame.json"),
+            Section::Ui => Some("saved_date"),
+            Section::Jobs | Section::Strategy => None,
+        }
+    }
+}
+
+// 配置文件的内容，各节在读取时才转换为对应的类型
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    #[serde(default)]
+    pub schema_version: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accounts: Option<Value>, // 账号，明文或加密
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub games: Option<Value>, // 游戏名称 -> 游戏配置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jobs: Option<Value>, // 定时任务
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<Value>, // 全局抢票策略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ui: Option<Value>, // 界面设置
+    #[serde(flatten)]
+    pub other: Map<String, Value>, // 不认识的配置项原样保留
+}
+
+impl Document {
+    fn new() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            ..Default::default()
+        }
+    }
+
+    fn section_mut(&mut self, section: Section) -> &mut Option<Value> {
+        match section {
+            Section::Accounts => &mut self.accounts,
+            Section::Games => &mut self.games,
+            Section::Jobs => &mut self.jobs,
+            Section::Strategy => &mut self.strategy,
+            Section::Ui => &mut self.ui,
+        }
+    }
+}
+
+// 界面设置
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiPrefs {
+    pub date: Option<NaiveDate>, // 定时抢票页面选择的日期
+    pub time: Option<NaiveTime>, // 定时抢票页面选择的时间
+}
+
+// 配置中的一个问题，field 是出错字段的路径，例如 jobs[0].zones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    pub field: String,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+impl From<Problem> for Error {
+    fn from(problem: Problem) -> Self {
+        Error::Invalid(problem)
+    }
+}
+
+// 读取的配置来自哪里
+#[derive(Debug, Clone, PartialEq)]
+enum Origin {
+    Current,                    // 当前版本的 config.json
+    Upgraded(u32),              // 旧版本的 config.json
+    Legacy(Vec<&'static Path>), // 旧版本分散保存的文件
+}
+
+pub fn dir() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("UTermux"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    Some(dir()?.join(FILE_NAME))
+}
+
+fn require_dir() -> Result<PathBuf> {
+    dir().ok_or_else(|| Error::Config("无法获取配置目录".to_string()))
+}
+
+fn join_field(parent: &str, path: &str) -> String {
+    match (parent, path) {
+        (parent, ".") => parent.to_string(),
+        ("", path) => path.to_string(),
+        (parent, path) if path.starts_with('[') => format!("{}{}", parent, path),
+        (parent, path) => format!("{}.{}", parent, path),
+    }
+}
+
+// 转换为对应的类型，出错时指出字段路径
+fn parse<T: DeserializeOwned>(field: &str, value: Value) -> std::result::Result<T, Problem> {
+    serde_path_to_error::deserialize(value).map_err(|e| {
+        Problem::new(
+            join_field(field, &e.path().to_string()),
+            e.into_inner().to_string(),
+        )
+    })
+}
+
+pub fn from_value<T: DeserializeOwned>(field: &str, value: Value) -> Result<T> {
+    Ok(parse(field, value)?)
+}
+
+fn parse_json(file: &str, content: &str) -> std::result::Result<Value, Problem> {
+    serde_json::from_str(content).map_err(|e| Problem::new(file, format!("JSON格式错误: {}", e)))
+}
+
+// 读取 config.json 并升级到当前版本
+fn read_document(path: &Path) -> Result<(Document, Origin)> {
+    let content = fs::read_to_string(path)?;
+    let mut document: Document = parse("", parse_json(FILE_NAME, &content)?)?;
+    let version = document.schema_version;
+    if version > SCHEMA_VERSION {
+        return Err(Problem::new(
+            "schema_version",
+            format!(
+                "配置文件的版本 {} 比程序支持的版本 {} 新，请升级 UTermux",
+                version, SCHEMA_VERSION
+            ),
+        )
+        .into());
+    }
+    for migrate in &MIGRATIONS[version as usize..] {
+        migrate(&mut document);
+    }
+    document.schema_version = SCHEMA_VERSION;
+    let origin = if version == SCHEMA_VERSION {
+        Origin::Current
+    } else {
+        Origin::Upgraded(version)
+    };
+    Ok((document, origin))
+}
+
+// 旧版本的 saved_date 保存的是 "2026-10-19 10:00:00" 或 "2026-10-19"
+fn legacy_ui(content: &str) -> std::result::Result<Value, Problem> {
+    let content = content.trim();
+    let invalid = || Problem::new("saved_date", format!("无法识别的日期 \"{}\"", content));
+    let prefs = if content.is_empty() {
+        UiPrefs::default()
+    } else if let Ok(datetime) = NaiveDateTime::parse_from_str(content, "%Y-%m-%d %H:%M:%S") {
+        UiPrefs {
+            date: Some(datetime.date()),
+            time: Some(datetime.time()),
+        }
+    } else {
+        UiPrefs {
+            date: Some(NaiveDate::parse_from_str(content, "%Y-%m-%d").map_err(|_| invalid())?),
+            time: None,
+        }
+    };
+    serde_json::to_value(prefs).map_err(|_| invalid())
+}
+
+// 合并旧版本分散保存的文件，没有任何旧文件时返回 None
+fn read_legacy(dir: &Path) -> Result<Option<(Document, Origin)>> {
+    let mut document = Document::new();
+    let mut files = vec![];
+    for section in Section::ALL {
+        let Some(file) = section.legacy_file() else {
+            continue;
+        };
+        let path = dir.join(file);
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)?;
+        let value = match section {
+            Section::Ui => legacy_ui(&content)?,
+            _ => parse_json(file, &content)?,
+        };
+        *document.section_mut(section) = Some(value);
+        files.push(Path::new(file));
+    }
+    Ok((!files.is_empty()).then_some((document, Origin::Legacy(files))))
+}
+
+// config.json 不存在时读取旧版本的文件
+fn read(dir: &Path) -> Result<Option<(Document, Origin)>> {
+    let path = dir.join(FILE_NAME);
+    if path.exists() {
+        read_document(&path).map(Some)
+    } else {
+        read_legacy(dir)
+    }
+}
+
+fn migrated_path(dir: &Path, file: &Path) -> PathBuf {
+    dir.join(format!("{}.migrated", file.display()))
+}
+
+// 修改并写回配置文件，读取失败时不写入，避免覆盖原文件
+fn write_document(
+    lock: &FileLock,
+    dir: &Path,
+    change: impl FnOnce(&mut Document) -> Result<()>,
+) -> Result<Origin> {
+    let (mut document, origin) = read(dir)?.unwrap_or((Document::new(), Origin::Current));
+    change(&mut document)?;
+    persist::write_locked(
+        lock,
+        &dir.join(FILE_NAME),
+        serde_json::to_string_pretty(&document)?,
+    )?;
+    // 旧文件的内容已经写入 config.json
+    if let Origin::Legacy(files) = &origin {
+        for file in files {
+            fs::rename(dir.join(file), migrated_path(dir, file))?;
+        }
+    }
+    Ok(origin)
+}
+
+fn lock_dir(dir: &Path) -> Result<FileLock> {
+    FileLock::acquire(&dir.join(FILE_NAME))
+}
+
+// 启动时合并旧版本的文件或升级旧版本的 config.json，返回给用户的说明
+pub fn init() -> Result<Option<String>> {
+    let Some(dir) = dir() else {
+        return Ok(None);
+    };
+    // config.json 格式错误时不需要迁移，由 check 指出错误
+    let path = dir.join(FILE_NAME);
+    let outdated = if path.exists() {
+        matches!(read_document(&path), Ok((_, Origin::Upgraded(_))))
+    } else {
+        read_legacy(&dir)?.is_some()
+    };
+    if !outdated {
+        return Ok(None);
+    }
+    let lock = lock_dir(&dir)?;
+    Ok(match write_document(&lock, &dir, |_| Ok(()))? {
+        Origin::Current => None,
+        Origin::Upgraded(version) => Some(format!(
+            "配置文件已从版本 {} 升级到版本 {}",
+            version, SCHEMA_VERSION
+        )),
+        Origin::Legacy(files) => Some(format!(
+            "已把 {} 合并到 {}，原文件改名为 *.migrated",
+            files
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>()
+                .join("、"),
+            FILE_NAME
+        )),
+    })
+}
+
+// 锁住配置文件，读取、修改、写回期间持有
+pub fn lock() -> Result<FileLock> {
+    lock_dir(&require_dir()?)
+}
+
+// 读取一节的原始内容，不存在时返回 None
+pub fn load_value(section: Section) -> Result<Option<Value>> {
+    let Some(dir) = dir() else {
+        return Ok(None);
+    };
+    Ok(read(&dir)?.and_then(|(mut document, _)| document.section_mut(section).take()))
+}
+
+// 读取一节，不存在时使用默认值
+pub fn load<T: DeserializeOwned + Default>(section: Section) -> Result<T> {
+    match load_value(section)? {
+        Some(value) => from_value(section.name(), value),
+        None => Ok(T::default()),
+    }
+}
+
+pub fn save_locked(lock: &FileLock, section: Section, value: &impl Serialize) -> Result<()> {
+    let value = serde_json::to_value(value)?;
+    write_document(lock, &require_dir()?, |document| {
+        *document.section_mut(section) = Some(value);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+// 读取、修改并保存一节，期间一直持有锁。读取或修改失败时不保存，内容没有变化时不写入
+pub fn update<T: DeserializeOwned + Serialize + Default, R>(
+    section: Section,
+    change: impl FnOnce(&mut T) -> Result<R>,
+) -> Result<R> {
+    let lock = lock()?;
+    let mut value: T = load(section)?;
+    let before = serde_json::to_value(&value)?;
+    let result = change(&mut value)?;
+    if serde_json::to_value(&value)? != before {
+        save_locked(&lock, section, &value)?;
+    }
+    Ok(result)
+}
+
+// 删除可能含有明文账号的旧文件：配置文件的备份和迁移前的 auth_token.json
+pub fn remove_plaintext_copies() -> Result<()> {
+    let Some(dir) = dir() else {
+        return Ok(());
+    };
+    persist::remove_backups(&dir.join(FILE_NAME))?;
+    if let Some(legacy) = Section::Accounts.legacy_file() {
+        persist::remove_backups(&dir.join(legacy))?;
+        let migrated = migrated_path(&dir, Path::new(legacy));
+        if migrated.exists() {
+            fs::remove_file(migrated)?;
+        }
+    }
+    Ok(())
+}
+
+// 检查配置文件，返回发现的问题
+pub fn check() -> Result<Vec<Problem>> {
+    let dir = require_dir()?;
+    match read(&dir) {
+        Ok(Some((document, _))) => Ok(check_document(document)),
+        Ok(None) => Ok(vec![]),
+        Err(Error::Invalid(problem)) => Ok(vec![problem]),
+        Err(e) => Err(e),
+    }
+}
+
+fn error_message(error: Error) -> String {
+    match error {
+        Error::Config(message) => message,
+        Error::Invalid(problem) => problem.message,
+        error => error.to_string(),
+    }
+}
+
+fn check_strategy(field: &str, strategy: &ReceiveStrategy, problems: &mut Vec<Problem>) {
+    let mut require = |ok: bool, name: &str, message: &str| {
+        if !ok {
+            problems.push(Problem::new(format!("{}.{}", field, name), message));
+        }
+    };
+    require(strategy.workers > 0, "workers", "并发任务数至少为1");
+    require(
+        strategy.mode != ReceiveMode::Burst || strategy.burst_count > 0,
+        "burst_count",
+        "爆发模式下同时发出的请求数至少为1",
+    );
+    require(strategy.window_ms > 0, "window_ms", "抢票窗口不能为0");
+    require(
+        strategy.request_timeout_ms > 0,
+        "request_timeout_ms",
+        "请求超时不能为0",
+    );
+}
+
+// 检查各节的格式和内容，一节格式错误时跳过这一节的其他检查
+pub fn check_document(mut document: Document) -> Vec<Problem> {
+    let mut problems = vec![];
+    for key in document.other.keys() {
+        problems.push(Problem::new(key.clone(), "未知的配置项"));
+    }
+
+    if let Some(value) = document.accounts.take() {
+        let storage = match value.get("encrypted") {
+            Some(encrypted) => parse::<Sealed>("accounts.encrypted", encrypted.clone())
+                .map_err(|problem| problems.push(problem))
+                .ok()
+                // 未解锁时无法检查加密的账号
+                .and_then(|sealed| Some((vault::unlocked()?, sealed)))
+                .and_then(|(vault, sealed)| match vault.open(&sealed) {
+                    Ok(plaintext) => serde_json::from_slice(&plaintext).ok(),
+                    Err(e) => {
+                        problems.push(Problem::new("accounts.encrypted", error_message(e)));
+                        None
+                    }
+                }),
+            None => parse::<TokenStorage>("accounts", value)
+                .map_err(|problem| problems.push(problem))
+                .ok(),
+        };
+        let mut phones: HashMap<&str, usize> = HashMap::new();
+        for (i, record) in storage.iter().flat_map(|s| s.records.iter()).enumerate() {
+            let field = |name: &str| format!("accounts.records[{}].{}", i, name);
+            if record.auth_token.trim().is_empty() {
+                problems.push(Problem::new(field("auth_token"), "token不能为空"));
+            }
+            if record.mobile_phone.trim().is_empty() {
+                problems.push(Problem::new(field("mobile_phone"), "手机号不能为空"));
+            } else if let Some(first) = phones.insert(&record.mobile_phone, i) {
+                problems.push(Problem::new(
+                    field("mobile_phone"),
+                    format!("与 accounts.records[{}] 是同一个账号", first),
+                ));
+            }
+        }
+    }
+
+    if let Some(value) = document.games.take() {
+        if let Ok(games) = parse::<GameConfigs>("games", value).map_err(|p| problems.push(p)) {
+            let mut names: Vec<&String> = games.keys().collect();
+            names.sort();
+            for name in names {
+                if games[name].communityId.trim().is_empty() {
+                    problems.push(Problem::new(
+                        format!("games.{}.communityId", name),
+                        "communityId 不能为空",
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(value) = document.jobs.take() {
+        if let Ok(jobs) = parse::<Vec<Job>>("jobs", value).map_err(|p| problems.push(p)) {
+            let mut ids: HashMap<u64, usize> = HashMap::new();
+            for (i, job) in jobs.iter().enumerate() {
+                let field = |name: &str| format!("jobs[{}].{}", i, name);
+                if let Some(first) = ids.insert(job.id, i) {
+                    problems.push(Problem::new(
+                        field("id"),
+                        format!("与 jobs[{}] 的编号 {} 重复", first, job.id),
+                    ));
+                }
+                if job.zones.is_empty() {
+                    problems.push(Problem::new(field("zones"), "至少需要一个专区"));
+                }
+                if let Err(e) = job.repeat.validate() {
+                    problems.push(Problem::new(field("repeat"), error_message(e)));
+                }
+                if let Some(strategy) = &job.strategy {
+                    check_strategy(&field("strategy"), strategy, &mut problems);
+                }
+            }
+        }
+    }
+
+    if let Some(value) = document.strategy.take() {
+        if let Ok(strategy) = parse("strategy", value).map_err(|p| problems.push(p)) {
+            check_strategy("strategy", &strategy, &mut problems);
+        }
+    }
+
+    if let Some(value) = document.ui.take() {
+        if let Err(problem) = parse::<UiPrefs>("ui", value) {
+            problems.push(problem);
+        }
+    }
+    problems
+}
+
+#[test]
+fn test_migrate_legacy() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir = dir.path();
+    fs::write(
+        dir.join("auth_token.json"),
+        r#"{"records":[{"auth_token":"t","mobile_phone":"138","user_agent":"ua","active":true}]}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("AppGame.json"),
+        r#"{"三国杀":{"active":true,"communityId":"14","red_pack_tasks":{}}}"#,
+    )
+    .unwrap();
+    fs::write(dir.join("saved_date"), "2026-10-19 10:00:00").unwrap();
+    // 旧版本没有这个文件，不会被合并
+    fs::write(dir.join("AppJobs.json"), r#"{"jobs":[]}"#).unwrap();
+
+    let lock = lock_dir(dir).unwrap();
+    let origin = write_document(&lock, dir, |_| Ok(())).unwrap();
+    assert!(matches!(origin, Origin::Legacy(files) if files.len() == 3));
+    assert!(!dir.join("auth_token.json").exists());
+    assert!(dir.join("auth_token.json.migrated").exists());
+    assert!(dir.join("AppJobs.json").exists());
+
+    let (document, origin) = read(dir).unwrap().unwrap();
+    assert_eq!(origin, Origin::Current);
+    assert_eq!(document.schema_version, SCHEMA_VERSION);
+    let ui: UiPrefs = from_value("ui", document.ui.clone().unwrap()).unwrap();
+    assert_eq!(ui.time, NaiveTime::from_hms_opt(10, 0, 0));
+    assert_eq!(check_document(document), vec![]);
+
+    // 格式错误的旧文件不会被合并，也不会被覆盖
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("AppGame.json"), "{ broken").unwrap();
+    assert!(write_document(&lock_dir(dir.path()).unwrap(), dir.path(), |_| Ok(())).is_err());
+    assert!(dir.path().join("AppGame.json").exists());
+}
+
+#[test]
+fn test_check_document() {
+    let document: Document = serde_json::from_value(serde_json::json!({
+        "schema_version": 1,
+        "jobs": [
+            { "id": 1, "target": "2026-10-19T10:00:00", "zones": [] },
+            { "id": 1, "target": "2026-10-19T10:00:00", "zones": ["每日专区"],
+              "repeat": { "Cron": "bad" } },
+        ],
+        "strategy": { "workers": "two" },
+        "ui": { "date": "2026-13-01" },
+        "theme": "dark",
+    }))
+    .unwrap();
+    let fields: Vec<String> = check_document(document)
+        .into_iter()
+        .map(|problem| problem.field)
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "theme",
+            "jobs[0].zones",
+            "jobs[1].id",
+            "jobs[1].repeat",
+            "strategy.workers",
+            "ui.date"
+        ]
+    );
+
+    // 新版本程序写入的文件不会被旧版本读取后覆盖
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join(FILE_NAME), r#"{"schema_version": 99}"#).unwrap();
+    assert!(matches!(
+        read(dir.path()),
+        Err(Error::Invalid(problem)) if problem.field == "schema_version"
+    ));
+
+    // 读取一节出错时返回出错的字段，而不是空的默认值
+    let jobs =
+        serde_json::json!([{ "id": 1, "target": "2026-10-19T10:00:00", "zones": "每日专区" }]);
+    assert!(matches!(
+        from_value::<Vec<Job>>("jobs", jobs),
+        Err(Error::Invalid(problem)) if problem.field == "jobs[0].zones"
+    ));
+}
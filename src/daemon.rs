@@ -0,0 +1,122 @@
+// 无界面模式：在服务器上后台运行定时任务
+//
+// 从配置目录读取账号、游戏配置和定时任务，到时间后执行与界面相同的抢票逻辑，
+// 事件以 JSON 逐行写入日志文件，收到 SIGTERM 或 Ctrl+C 时取消所有任务后退出。
+
+use chrono::Local;
+use color_eyre::eyre::eyre;
+use color_eyre::Result;
+use std::fs::{self, OpenOptions};
+use std::path::PathBuf;
+use tokio::sync::watch;
+use UTermux::config;
+use UTermux::engine::accounts;
+use UTermux::engine::events::{Events, LogSink};
+use UTermux::engine::jobs::JobList;
+use UTermux::engine::runner::{JobRunner, RELOAD_INTERVAL};
+use UTermux::utils::request::{RequestConfig, RequestContext};
+use UTermux::utils::scheduler::Countdowns;
+
+// 默认日志文件
+pub fn default_log_path() -> Option<PathBuf> {
+    let config_dir = dirs::config_dir()?;
+    Some(config_dir.join("UTermux").join("daemon.log"))
+}
+
+pub async fn run(log_path: Option<PathBuf>) -> Result<()> {
+    let log_path = log_path
+        .or_else(default_log_path)
+        .ok_or_else(|| eyre!("无法确定日志文件位置"))?;
+    let request_context = RequestContext::new(RequestConfig::from_env())?;
+    let (sender, mut receiver) = Events::channel(100);
+    let countdown = watch::Sender::new(Countdowns::new());
+    // 退出前一直持有，界面在此期间只添加和修改任务，不执行任务
+    let mut runner = JobRunner::claim(request_context.clone(), sender.clone(), countdown)?
+        .ok_or_else(|| eyre!("界面或另一个后台模式正在执行定时任务，请先退出后再启动后台模式"))?;
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let log_file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)?;
+    println!("UTermux 后台运行中，日志写入 {}", log_path.display());
+
+    // 抢票逻辑的事件逐行写入日志文件
+    let writer = tokio::spawn(async move {
+        let mut sink = LogSink::new(log_file);
+        while let Some(event) = receiver.recv().await {
+            let _ = sink.write(event);
+        }
+    });
+
+    sender.message("后台模式已启动").await;
+    if let Ok(problems) = config::check() {
+        for problem in problems {
+            sender.message(format!("配置有误 {}", problem)).await;
+        }
+    }
+
+    // 错过时间的任务顺延或标记为已过期
+    if let Err(e) = JobList::update(|jobs| Ok(jobs.arm(Local::now().naive_local()))) {
+        sender.message(format!("保存定时任务失败: {}", e)).await;
+    }
+
+    // 定期校验账号token，失效的账号不参与抢票
+    let validator = tokio::spawn(accounts::validate_periodically(
+        request_context.clone(),
+        accounts::VALIDATE_INTERVAL,
+        sender.clone(),
+    ));
+
+    // 信号处理只注册一次，否则每轮循环之间收到的信号会丢失
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+    let mut reload = tokio::time::interval(RELOAD_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = reload.tick() => match runner.sync() {
+                Ok(started) => {
+                    for job in started {
+                        sender.message(format!("已加载定时任务 {}", job.describe())).await;
+                    }
+                }
+                Err(e) => sender.message(format!("读取定时任务失败: {}", e)).await,
+            },
+            _ = &mut shutdown => {
+                sender.message("收到退出信号，正在取消所有任务").await;
+                break;
+            }
+        }
+    }
+
+    validator.abort();
+    drop(runner);
+    sender.message("后台模式已退出").await;
+    drop(sender);
+    let _ = writer.await;
+    Ok(())
+}
+
+// 等待 SIGTERM 或 Ctrl+C
+#[cfg(unix)]
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        Err(_) => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn shutdown_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
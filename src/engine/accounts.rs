@@ -0,0 +1,488 @@
+use crate::api::code::Outcome;
+use crate::api::queryMobilePhone::{
+    fetch_account_phone, fetch_mobile_phone, read_saved_account, save_token,
+};
+pub use crate::api::queryMobilePhone::{TokenRecord, TokenStorage};
+use crate::engine::events::{Events, GrabEvent};
+use crate::engine::transfer::{self, AccountEntry, Format};
+use crate::error::{Error, Result};
+use crate::utils::request::RequestContext;
+use chrono::Local;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+// 定期校验账号token的间隔
+pub const VALIDATE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// 同时校验、导入的账号数
+const VALIDATE_CONCURRENCY: usize = 4;
+
+// 校验token并保存账号，返回账号的手机号
+pub async fn login(ctx: &RequestContext, auth_token: &str, user_agent: &str) -> Result<String> {
+    let mobile_phone = fetch_mobile_phone(ctx, auth_token, user_agent).await?;
+    save_token(auth_token, &mobile_phone, user_agent)?;
+    Ok(mobile_phone)
+}
+
+// 已保存的账号
+pub fn list() -> Result<Vec<TokenRecord>> {
+    Ok(TokenStorage::load()?.records)
+}
+
+// 账号文件已加密且本次运行尚未输入口令
+pub fn is_locked() -> bool {
+    TokenStorage::is_locked()
+}
+
+// 输入口令解锁加密的账号文件，之后的读写都在内存中加解密
+pub fn unlock(passphrase: &str) -> Result<Vec<TokenRecord>> {
+    Ok(TokenStorage::unlock(passphrase)?.records)
+}
+
+// 把明文的账号文件转换为用口令加密的格式
+pub fn encrypt(passphrase: &str) -> Result<Vec<TokenRecord>> {
+    Ok(TokenStorage::encrypt(passphrase)?.records)
+}
+
+fn position(storage: &TokenStorage, mobile_phone: &str) -> Result<usize> {
+    storage
+        .records
+        .iter()
+        .position(|r| r.mobile_phone == mobile_phone)
+        .ok_or_else(|| Error::Config(format!("找不到账号 {}", mobile_phone)))
+}
+
+// 修改账号列表并保存，返回修改后的列表；读取失败时不保存，避免覆盖原文件
+fn update(change: impl FnOnce(&mut TokenStorage) -> Result<()>) -> Result<Vec<TokenRecord>> {
+    Ok(TokenStorage::update(change)?.records)
+}
+
+// 激活账号，only 为真时同时停用其他账号
+pub fn activate(mobile_phone: &str, only: bool) -> Result<Vec<TokenRecord>> {
+    update(|storage| {
+        let index = position(storage, mobile_phone)?;
+        for (i, record) in storage.records.iter_mut().enumerate() {
+            if i == index {
+                record.active = true;
+            } else if only {
+                record.active = false;
+            }
+        }
+        Ok(())
+    })
+}
+
+pub fn deactivate(mobile_phone: &str) -> Result<Vec<TokenRecord>> {
+    update(|storage| {
+        let index = position(storage, mobile_phone)?;
+        storage.records[index].active = false;
+        Ok(())
+    })
+}
+
+// 切换列表中第 index 个账号的激活状态
+pub fn toggle(index: usize) -> Result<Vec<TokenRecord>> {
+    update(|storage| {
+        let record = storage
+            .records
+            .get_mut(index)
+            .ok_or_else(|| Error::Config(format!("找不到第{}个账号", index + 1)))?;
+        record.active = !record.active;
+        Ok(())
+    })
+}
+
+pub fn remove(mobile_phone: &str) -> Result<Vec<TokenRecord>> {
+    update(|storage| remove_record(storage, mobile_phone).map(|_| ()))
+}
+
+fn remove_record(storage: &mut TokenStorage, mobile_phone: &str) -> Result<TokenRecord> {
+    let index = position(storage, mobile_phone)?;
+    Ok(storage.records.remove(index))
+}
+
+// 设置账号备注，备注为空时清除
+pub fn rename(mobile_phone: &str, alias: Option<&str>) -> Result<Vec<TokenRecord>> {
+    update(|storage| rename_record(storage, mobile_phone, alias))
+}
+
+fn rename_record(
+    storage: &mut TokenStorage,
+    mobile_phone: &str,
+    alias: Option<&str>,
+) -> Result<()> {
+    let index = position(storage, mobile_phone)?;
+    storage.records[index].alias = alias
+        .map(str::trim)
+        .filter(|alias| !alias.is_empty())
+        .map(String::from);
+    Ok(())
+}
+
+// 调整账号顺序，offset 为负时上移，到达列表两端后不再移动
+fn move_record(storage: &mut TokenStorage, mobile_phone: &str, offset: isize) -> Result<usize> {
+    let index = position(storage, mobile_phone)?;
+    let target = index
+        .saturating_add_signed(offset)
+        .min(storage.records.len() - 1);
+    let record = storage.records.remove(index);
+    storage.records.insert(target, record);
+    Ok(target)
+}
+
+pub fn move_by(mobile_phone: &str, offset: isize) -> Result<Vec<TokenRecord>> {
+    update(|storage| move_record(storage, mobile_phone, offset).map(|_| ()))
+}
+
+// 用新的token替换账号原来的token，新token必须属于同一个手机号
+pub async fn replace_token(
+    ctx: &RequestContext,
+    mobile_phone: &str,
+    auth_token: &str,
+    user_agent: &str,
+) -> Result<Vec<TokenRecord>> {
+    let record = list()?
+        .into_iter()
+        .find(|r| r.mobile_phone == mobile_phone)
+        .ok_or_else(|| Error::Config(format!("找不到账号 {}", mobile_phone)))?;
+    let user_agent = check_replacement(ctx, &record, auth_token, user_agent).await?;
+    update(|storage| {
+        let index = position(storage, mobile_phone)?;
+        storage.records[index].set_token(auth_token, &user_agent);
+        Ok(())
+    })
+}
+
+// 校验新token属于该账号，返回替换后使用的 User-Agent，未指定时沿用原来的
+async fn check_replacement(
+    ctx: &RequestContext,
+    record: &TokenRecord,
+    auth_token: &str,
+    user_agent: &str,
+) -> Result<String> {
+    let user_agent = if user_agent.is_empty() {
+        record.user_agent.as_str()
+    } else {
+        user_agent
+    };
+    let fetched = fetch_mobile_phone(ctx, auth_token, user_agent).await?;
+    if fetched != record.mobile_phone {
+        return Err(Error::Config(format!(
+            "新的token属于账号 {}，不是 {}",
+            fetched, record.mobile_phone
+        )));
+    }
+    Ok(user_agent.to_string())
+}
+
+// 批量导入账号：查询手机号校验token并按文件中的顺序报告进度，校验通过的账号最后一次性保存。
+// 文件中没有 user_agent 的账号使用 user_agent 参数
+pub async fn import(
+    ctx: &RequestContext,
+    entries: Vec<AccountEntry>,
+    user_agent: &str,
+    events: &Events,
+) -> Result<Vec<TokenRecord>> {
+    let total = entries.len();
+    let permits = Arc::new(Semaphore::new(VALIDATE_CONCURRENCY));
+    let mut set = JoinSet::new();
+    for (index, mut entry) in entries.into_iter().enumerate() {
+        if entry.user_agent.is_empty() {
+            entry.user_agent = user_agent.to_string();
+        }
+        let ctx = ctx.clone();
+        let permits = permits.clone();
+        set.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let result = check_entry(&ctx, &entry).await;
+            (index, entry, result)
+        });
+    }
+
+    // 先完成的结果暂存，前面的账号都报告后再按顺序报告，全部结束后报告剩余的
+    let mut finished = BTreeMap::new();
+    let mut next = 0;
+    let mut imported = vec![];
+    while let Some(joined) = set.join_next().await {
+        if let Ok((index, entry, result)) = joined {
+            finished.insert(index, (entry, result));
+        }
+        while let Some(ready) = finished
+            .first_entry()
+            .filter(|ready| *ready.key() == next || set.is_empty())
+        {
+            let (index, (entry, result)) = ready.remove_entry();
+            let (account, error) = match result {
+                Ok(mobile_phone) => {
+                    imported.push((mobile_phone.clone(), entry));
+                    (Some(mobile_phone), None)
+                }
+                Err(e) => (entry.mobile_phone, Some(e.to_string())),
+            };
+            next = index + 1;
+            events
+                .send(GrabEvent::AccountImported {
+                    index: next,
+                    total,
+                    account,
+                    error,
+                })
+                .await;
+        }
+    }
+    events
+        .message(format!(
+            "导入完成：成功{}个，失败{}个",
+            imported.len(),
+            total - imported.len()
+        ))
+        .await;
+
+    // 按文件中的顺序保存，同一账号出现多次时以最后一次为准
+    update(|storage| {
+        for (mobile_phone, entry) in imported {
+            merge_entry(storage, mobile_phone, entry);
+        }
+        Ok(())
+    })
+}
+
+// 校验导入的token，返回token所属的手机号
+async fn check_entry(ctx: &RequestContext, entry: &AccountEntry) -> Result<String> {
+    if entry.is_redacted() {
+        return Err(Error::Config("token已隐藏，无法导入".to_string()));
+    }
+    let mobile_phone = fetch_mobile_phone(ctx, &entry.auth_token, &entry.user_agent).await?;
+    match &entry.mobile_phone {
+        Some(expected) if expected != &mobile_phone => Err(Error::Config(format!(
+            "token属于账号 {}，不是 {}",
+            mobile_phone, expected
+        ))),
+        _ => Ok(mobile_phone),
+    }
+}
+
+// 已有的账号替换token，文件中填写了的字段覆盖原来的设置；新账号添加到末尾
+fn merge_entry(storage: &mut TokenStorage, mobile_phone: String, entry: AccountEntry) {
+    let index = match storage
+        .records
+        .iter()
+        .position(|r| r.mobile_phone == mobile_phone)
+    {
+        Some(index) => index,
+        None => {
+            storage.records.push(TokenRecord {
+                auth_token: String::new(),
+                active: storage.records.is_empty(), // 第一个账号默认激活
+                mobile_phone,
+                user_agent: String::new(),
+                headers: Default::default(),
+                valid: None,
+                last_validated_at: None,
+                alias: None,
+            });
+            storage.records.len() - 1
+        }
+    };
+    let record = &mut storage.records[index];
+    record.set_token(&entry.auth_token, &entry.user_agent);
+    if entry.alias.is_some() {
+        record.alias = entry.alias;
+    }
+    if let Some(active) = entry.active {
+        record.active = active;
+    }
+    if !entry.headers.is_empty() {
+        record.headers = entry.headers;
+    }
+}
+
+// 导出账号，未指定手机号时导出全部账号
+pub fn export(mobile_phones: &[String], format: Format, redact: bool) -> Result<String> {
+    // 完整导出用于迁移账号，不能丢失字段
+    if !redact && !format.is_lossless() {
+        return Err(Error::Config(
+            "文本格式只包含token和手机号，完整导出请使用 json 或 csv 格式".to_string(),
+        ));
+    }
+    let records = TokenStorage::load()?.records;
+    let mut entries = vec![];
+    for record in &records {
+        if mobile_phones.is_empty() || mobile_phones.contains(&record.mobile_phone) {
+            entries.push(AccountEntry::from_record(record, redact));
+        }
+    }
+    if let Some(missing) = mobile_phones
+        .iter()
+        .find(|phone| !records.iter().any(|r| &&r.mobile_phone == phone))
+    {
+        return Err(Error::Config(format!("找不到账号 {}", missing)));
+    }
+    transfer::format(&entries, format)
+}
+
+// 任务使用的账号：指定手机号时使用指定的账号，否则使用当前激活的账号
+pub fn resolve(mobile_phones: &[String]) -> Result<Option<TokenRecord>> {
+    read_saved_account(mobile_phones.first().map(String::as_str))
+}
+
+// 任务使用的所有账号：指定手机号时按顺序使用指定的账号，否则使用所有激活的账号
+pub fn resolve_all(mobile_phones: &[String]) -> Result<Vec<TokenRecord>> {
+    let records = list()?;
+    if mobile_phones.is_empty() {
+        return Ok(records.into_iter().filter(|r| r.active).collect());
+    }
+    Ok(mobile_phones
+        .iter()
+        .filter_map(|mobile_phone| records.iter().find(|r| &r.mobile_phone == mobile_phone))
+        .cloned()
+        .collect())
+}
+
+// 记录账号token的校验结果
+pub fn record_validation(results: &[(String, bool)]) -> Result<Vec<TokenRecord>> {
+    let now = Local::now().naive_local();
+    update(|storage| {
+        for (mobile_phone, valid) in results {
+            if let Some(record) = storage
+                .records
+                .iter_mut()
+                .find(|r| &r.mobile_phone == mobile_phone)
+            {
+                record.valid = Some(*valid);
+                record.last_validated_at = Some(now);
+            }
+        }
+        Ok(())
+    })
+}
+
+// 查询每个账号的手机号校验token并保存结果，网络错误等无法判断的账号保留原来的状态
+pub async fn validate(ctx: &RequestContext, events: &Events) -> Result<Vec<TokenRecord>> {
+    let permits = Arc::new(Semaphore::new(VALIDATE_CONCURRENCY));
+    let mut set = JoinSet::new();
+    for record in list()? {
+        let ctx = ctx.clone();
+        let permits = permits.clone();
+        set.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            let result = fetch_account_phone(&ctx, &record.headers()).await;
+            (record.mobile_phone, result)
+        });
+    }
+
+    let mut results = vec![];
+    while let Some(joined) = set.join_next().await {
+        let Ok((mobile_phone, result)) = joined else {
+            continue;
+        };
+        // 只有确认登录失效才记为失效，网络错误、限流等下次再校验
+        let valid = match result {
+            Ok(_) => true,
+            Err(e) if e.outcome() == Outcome::AuthFailure => false,
+            Err(e) => {
+                events
+                    .message(format!("账号 {} 校验失败: {}", mobile_phone, e))
+                    .await;
+                continue;
+            }
+        };
+        events
+            .send(GrabEvent::AccountChecked {
+                account: mobile_phone.clone(),
+                valid,
+            })
+            .await;
+        results.push((mobile_phone, valid));
+    }
+
+    record_validation(&results)
+}
+
+// 按固定间隔校验所有账号，立即开始第一次校验
+pub async fn validate_periodically(ctx: RequestContext, interval: Duration, events: Events) {
+    loop {
+        if let Err(e) = validate(&ctx, &events).await {
+            events.message(format!("保存账号校验结果失败: {}", e)).await;
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[cfg(test)]
+fn test_record(mobile_phone: &str) -> TokenRecord {
+    TokenRecord {
+        auth_token: String::new(),
+        mobile_phone: mobile_phone.to_string(),
+        user_agent: String::new(),
+        active: false,
+        headers: Default::default(),
+        valid: None,
+        last_validated_at: None,
+        alias: None,
+    }
+}
+
+#[test]
+fn test_move_record() {
+    let mut storage = TokenStorage {
+        records: vec![test_record("1"), test_record("2"), test_record("3")],
+    };
+    let order = |storage: &TokenStorage| -> Vec<String> {
+        storage
+            .records
+            .iter()
+            .map(|r| r.mobile_phone.clone())
+            .collect()
+    };
+
+    assert_eq!(move_record(&mut storage, "3", -1).unwrap(), 1);
+    assert_eq!(order(&storage), ["1", "3", "2"]);
+    // 到达两端后不再移动
+    assert_eq!(move_record(&mut storage, "1", -1).unwrap(), 0);
+    assert_eq!(move_record(&mut storage, "2", 1).unwrap(), 2);
+    assert_eq!(order(&storage), ["1", "3", "2"]);
+    assert!(move_record(&mut storage, "4", 1).is_err());
+}
+
+#[test]
+fn test_remove_and_rename_record() {
+    let mut storage = TokenStorage {
+        records: vec![test_record("1"), test_record("2")],
+    };
+
+    rename_record(&mut storage, "2", Some("  小号 ")).unwrap();
+    assert_eq!(storage.records[1].alias.as_deref(), Some("小号"));
+    // 备注为空时清除
+    rename_record(&mut storage, "2", Some(" ")).unwrap();
+    assert_eq!(storage.records[1].alias, None);
+    assert!(rename_record(&mut storage, "3", Some("x")).is_err());
+
+    assert_eq!(remove_record(&mut storage, "1").unwrap().mobile_phone, "1");
+    assert_eq!(storage.records.len(), 1);
+    assert!(remove_record(&mut storage, "1").is_err());
+}
+
+#[tokio::test]
+async fn test_check_replacement() {
+    use crate::utils::mock_server::{MockServer, MOCK_MOBILE_PHONE, QUERY_MOBILE_PHONE};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let mut record = test_record(MOCK_MOBILE_PHONE);
+    record.user_agent = "old-agent".to_string();
+
+    // 未指定 User-Agent 时沿用原来的
+    let user_agent = check_replacement(&ctx, &record, "new-token", "").await;
+    assert_eq!(user_agent.unwrap(), "old-agent");
+    let request = server.requests_to(QUERY_MOBILE_PHONE).pop().unwrap();
+    assert_eq!(request.header("authToken"), Some("new-token"));
+    assert_eq!(request.header("user-agent"), Some("old-agent"));
+
+    // 新token属于其他账号时拒绝替换
+    let other = test_record("13900000000");
+    let result = check_replacement(&ctx, &other, "new-token", "new-agent").await;
+    assert!(matches!(result, Err(Error::Config(msg)) if msg.contains(MOCK_MOBILE_PHONE)));
+}
@@ -0,0 +1,403 @@
+use crate::error::Result;
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Write;
+use tokio::sync::mpsc;
+
+// 一次领取请求对应的账号和优惠券
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ticket {
+    pub account: String, // 账号手机号
+    pub game: String,    // 游戏名称
+    pub zone: String,    // 专区名称
+    pub task: String,    // redPackTaskId
+}
+
+// 一个账号本次抢票的结果统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrabStats {
+    pub account: String,  // 账号手机号
+    pub requested: usize, // 发出领取请求的优惠券数
+    pub succeeded: usize, // 领取成功
+    pub sold_out: usize,  // 已抢完或已领取
+    pub failed: usize,    // 其他失败
+}
+
+// 引擎运行过程中发出的事件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GrabEvent {
+    // 任务已校准时钟，等待开抢
+    JobScheduled {
+        job: u64,
+        target: NaiveDateTime,  // 开抢时间（服务器时间）
+        fire_at: NaiveDateTime, // 校准后的本地发出时间
+    },
+    // 按服务器时钟校准了本地时间，单位为毫秒，offset_ms 为正数表示服务器比本地快
+    Calibrated {
+        job: u64,
+        offset_ms: i64,
+        rtt_ms: i64,
+        uncertainty_ms: i64,
+        samples: usize,
+    },
+    // 校准失败，local 为真时使用本地时间，否则沿用上次校准的结果
+    CalibrationFailed {
+        job: u64,
+        local: bool,
+        reason: String,
+    },
+    // 开抢前预热了连接，valid 为校验通过的账号，invalid 为token已失效、不再参与的账号，
+    // unchecked 为预热失败或开抢前没有完成校验、仍然参与抢票的账号
+    WarmedUp {
+        job: u64,
+        valid: Vec<String>,
+        invalid: Vec<String>,
+        unchecked: Vec<String>,
+    },
+    // 账号不参与本次抢票
+    AccountSkipped {
+        job: u64,
+        account: String,
+        reason: String,
+    },
+    // 距离发出请求还有多久
+    Countdown {
+        job: u64,
+        seconds: u64,
+    },
+    // 到达开抢时间，开始发出领取请求
+    RequestSent {
+        job: u64,
+        count: usize,
+        late_ms: f64,
+    },
+    Succeeded {
+        job: u64,
+        #[serde(flatten)]
+        ticket: Ticket,
+    },
+    // ticket 为空时是整个任务或整个账号失败，例如token失效
+    Failed {
+        job: u64,
+        #[serde(flatten)]
+        ticket: Option<Ticket>,
+        reason: String,
+    },
+    // 按账号分别统计的结果
+    JobFinished {
+        job: u64,
+        stats: Vec<GrabStats>,
+    },
+    // 本次抢票遇到的未收录业务码
+    UnknownCode {
+        job: u64,
+        code: String,
+        msg: String,
+    },
+    // 获取了游戏的红包任务ID，失败时 error 为原因
+    RedPackTasksFetched {
+        game: String,
+        error: Option<String>,
+    },
+    // 校验了账号的token
+    AccountChecked {
+        account: String,
+        valid: bool,
+    },
+    // 批量导入账号的进度，index 从1开始，失败时 error 为原因
+    AccountImported {
+        index: usize,
+        total: usize,
+        account: Option<String>,
+        error: Option<String>,
+    },
+    // 其他进度信息
+    Message {
+        text: String,
+    },
+}
+
+impl GrabEvent {
+    pub fn message(text: impl Into<String>) -> Self {
+        GrabEvent::Message { text: text.into() }
+    }
+}
+
+// 界面和命令行显示的文字
+impl fmt::Display for GrabEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrabEvent::JobScheduled {
+                job,
+                target,
+                fire_at,
+            } => write!(
+                f,
+                "任务#{}定时抢票时间设置为: {}，校准后将在本地时间 {} 发出请求",
+                job,
+                target,
+                fire_at.format("%H:%M:%S%.3f")
+            ),
+            GrabEvent::Calibrated {
+                job,
+                offset_ms,
+                rtt_ms,
+                uncertainty_ms,
+                samples,
+            } => write!(
+                f,
+                "任务#{}服务器时间比本地{} {}ms（往返延迟 {}ms，误差 ±{}ms，采样 {} 次）",
+                job,
+                if *offset_ms >= 0 { "快" } else { "慢" },
+                offset_ms.abs(),
+                rtt_ms,
+                uncertainty_ms,
+                samples
+            ),
+            GrabEvent::CalibrationFailed { job, local, reason } => write!(
+                f,
+                "任务#{}校准服务器时间失败，使用{}: {}",
+                job,
+                if *local {
+                    "本地时间"
+                } else {
+                    "上次校准的结果"
+                },
+                reason
+            ),
+            GrabEvent::WarmedUp {
+                job,
+                valid,
+                invalid,
+                unchecked,
+            } => {
+                write!(f, "任务#{}已预热连接", job)?;
+                for (accounts, label) in [
+                    (valid, "校验通过"),
+                    (invalid, "token已失效，已跳过，请重新登录"),
+                    (unchecked, "未完成校验，仍然参与抢票"),
+                ] {
+                    if !accounts.is_empty() {
+                        write!(f, "；账号 {} {}", accounts.join("、"), label)?;
+                    }
+                }
+                Ok(())
+            }
+            GrabEvent::AccountSkipped {
+                job,
+                account,
+                reason,
+            } => write!(f, "任务#{}跳过账号 {}：{}", job, account, reason),
+            GrabEvent::Countdown { job, seconds } => {
+                write!(f, "任务#{}距离开抢还有{}秒", job, seconds)
+            }
+            GrabEvent::RequestSent {
+                job,
+                count,
+                late_ms,
+            } => write!(
+                f,
+                "任务#{}开始发送抢票请求...（共{}张，定时误差 {:.2}ms）",
+                job, count, late_ms
+            ),
+            GrabEvent::Succeeded { ticket, .. } => write!(
+                f,
+                "{}({}): 领取成功（账号 {}）",
+                ticket.game, ticket.zone, ticket.account
+            ),
+            GrabEvent::Failed {
+                ticket: Some(ticket),
+                reason,
+                ..
+            } => write!(
+                f,
+                "{}({})抢票失败：{}（账号 {}）",
+                ticket.game, ticket.zone, reason, ticket.account
+            ),
+            GrabEvent::Failed {
+                job,
+                ticket: None,
+                reason,
+            } => write!(f, "任务#{}失败：{}", job, reason),
+            GrabEvent::JobFinished { job, stats } => {
+                write!(f, "任务#{}抢票已完成", job)?;
+                for (i, stats) in stats.iter().enumerate() {
+                    write!(
+                        f,
+                        "{}账号 {} 成功{}张，已抢完{}张，失败{}张",
+                        if i == 0 { "：" } else { "；" },
+                        stats.account,
+                        stats.succeeded,
+                        stats.sold_out,
+                        stats.failed
+                    )?;
+                }
+                Ok(())
+            }
+            GrabEvent::UnknownCode { job, code, msg } => {
+                write!(f, "任务#{}遇到未收录的业务码 {}: {}", job, code, msg)
+            }
+            GrabEvent::RedPackTasksFetched { game, error: None } => {
+                write!(f, "成功获取 {} 的红包任务ID", game)
+            }
+            GrabEvent::RedPackTasksFetched {
+                game,
+                error: Some(error),
+            } => write!(f, "获取 {} 的红包任务ID失败: {}", game, error),
+            GrabEvent::AccountChecked {
+                account,
+                valid: true,
+            } => write!(f, "账号 {} 校验通过", account),
+            GrabEvent::AccountChecked {
+                account,
+                valid: false,
+            } => write!(f, "账号 {} 的token已失效，请重新登录", account),
+            GrabEvent::AccountImported {
+                index,
+                total,
+                account,
+                error,
+            } => {
+                write!(f, "导入账号 {}/{}", index, total)?;
+                if let Some(account) = account {
+                    write!(f, "（{}）", account)?;
+                }
+                match error {
+                    None => write!(f, "成功"),
+                    Some(error) => write!(f, "失败：{}", error),
+                }
+            }
+            GrabEvent::Message { text } => write!(f, "{}", text),
+        }
+    }
+}
+
+// 事件的发送端，接收端关闭后发出的事件直接丢弃
+#[derive(Debug, Clone)]
+pub struct Events {
+    sender: mpsc::Sender<GrabEvent>,
+}
+
+impl Events {
+    pub fn channel(buffer: usize) -> (Self, mpsc::Receiver<GrabEvent>) {
+        let (sender, receiver) = mpsc::channel(buffer);
+        (Self { sender }, receiver)
+    }
+
+    pub async fn send(&self, event: GrabEvent) {
+        let _ = self.sender.send(event).await;
+    }
+
+    pub async fn message(&self, text: impl Into<String>) {
+        self.send(GrabEvent::message(text)).await;
+    }
+}
+
+// 日志中的一行：事件和发生时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub time: NaiveDateTime,
+    #[serde(flatten)]
+    pub event: GrabEvent,
+}
+
+// 把事件序列化为 JSON，每行一个，便于其他程序读取
+pub struct LogSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LogSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write(&mut self, event: GrabEvent) -> Result<()> {
+        let record = LogRecord {
+            time: Local::now().naive_local(),
+            event,
+        };
+        serde_json::to_writer(&mut self.writer, &record)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[test]
+fn test_log_sink() {
+    let ticket = Ticket {
+        account: "13800000000".to_string(),
+        game: "三国杀".to_string(),
+        zone: "每日专区".to_string(),
+        task: "59623".to_string(),
+    };
+    let events = vec![
+        GrabEvent::Succeeded {
+            job: 1,
+            ticket: ticket.clone(),
+        },
+        GrabEvent::Failed {
+            job: 1,
+            ticket: None,
+            reason: "账号token已失效".to_string(),
+        },
+        GrabEvent::JobFinished {
+            job: 1,
+            stats: vec![GrabStats {
+                account: "13800000000".to_string(),
+                requested: 1,
+                succeeded: 1,
+                ..Default::default()
+            }],
+        },
+        GrabEvent::WarmedUp {
+            job: 1,
+            valid: vec!["13800000000".to_string()],
+            invalid: vec!["13900000000".to_string()],
+            unchecked: vec![],
+        },
+        GrabEvent::UnknownCode {
+            job: 1,
+            code: "9999".to_string(),
+            msg: "未知错误".to_string(),
+        },
+        GrabEvent::message("后台模式已启动"),
+    ];
+
+    let mut sink = LogSink::new(Vec::new());
+    for event in events.clone() {
+        sink.write(event).unwrap();
+    }
+    let log = String::from_utf8(sink.into_inner()).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), events.len());
+
+    // 优惠券字段展开在事件中
+    let value: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(value["type"], "succeeded");
+    assert_eq!(value["game"], "三国杀");
+    assert_eq!(value["task"], "59623");
+    assert!(value["time"].is_string());
+
+    // 每一行都能还原为原来的事件
+    let parsed: Vec<GrabEvent> = lines
+        .iter()
+        .map(|line| serde_json::from_str::<LogRecord>(line).unwrap().event)
+        .collect();
+    assert_eq!(parsed, events);
+    assert_eq!(parsed[1].to_string(), "任务#1失败：账号token已失效");
+    assert_eq!(
+        parsed[2].to_string(),
+        "任务#1抢票已完成：账号 13800000000 成功1张，已抢完0张，失败0张"
+    );
+    assert_eq!(
+        parsed[3].to_string(),
+        "任务#1已预热连接；账号 13800000000 校验通过；账号 13900000000 token已失效，已跳过，请重新登录"
+    );
+}
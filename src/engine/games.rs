@@ -0,0 +1,203 @@
+use crate::api::info::get_info;
+use crate::api::queryMobilePhone::read_saved_token;
+use crate::api::receive::ZoneSelection;
+use crate::config::{self, Section};
+use crate::engine::events::{Events, GrabEvent};
+use crate::error::Result;
+use crate::utils::request::RequestContext;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// 默认游戏列表
+pub const DEFAULT_GAMES: [&str; 3] = ["三国杀", "王者荣耀", "火影忍者"];
+
+// 领券中心的专区
+pub const ZONES: [&str; 4] = ["置顶专区", "新人专区", "每周专区", "每日专区"];
+
+// 游戏在领券中心的 communityId
+pub fn community_id(game: &str) -> &'static str {
+    match game {
+        "王者荣耀" => "7",
+        "火影忍者" => "10",
+        _ => "14",
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GameConfig {
+    pub active: bool,
+    pub communityId: String,
+    #[serde(flatten)]
+    pub amounts: HashMap<String, u32>,
+    pub red_pack_tasks: HashMap<String, Vec<String>>, // 专区名称 -> redPackTaskId
+    #[serde(default)]
+    pub zone_ids: HashMap<String, i64>, // 专区名称 -> zoneId，来自领券中心数据
+    #[serde(default)]
+    pub zone_selection: HashMap<String, ZoneSelection>, // 手动指定的专区 zoneId
+}
+
+impl GameConfig {
+    pub fn new(game: &str, active: bool) -> Self {
+        Self {
+            active,
+            communityId: community_id(game).to_string(),
+            amounts: HashMap::new(),
+            red_pack_tasks: HashMap::new(),
+            zone_ids: HashMap::new(),
+            zone_selection: HashMap::new(),
+        }
+    }
+
+    // 专区使用的 zoneId：优先手动指定，其次使用领券中心返回的，都没有时为空
+    pub fn zone_selection(&self, zone: &str) -> Option<ZoneSelection> {
+        self.zone_selection
+            .get(zone)
+            .filter(|selection| !selection.zone_ids().is_empty())
+            .cloned()
+            .or_else(|| self.zone_ids.get(zone).map(|id| ZoneSelection::Pinned(*id)))
+    }
+}
+
+// 游戏名称 -> 游戏配置（配置文件的 games 节）
+pub type GameConfigs = HashMap<String, GameConfig>;
+
+// 读取游戏配置，没有保存过时返回空，格式错误时返回出错的字段
+pub fn load() -> Result<GameConfigs> {
+    config::load(Section::Games)
+}
+
+// 读取、修改并保存游戏配置，期间一直持有锁
+pub fn update<R>(change: impl FnOnce(&mut GameConfigs) -> Result<R>) -> Result<R> {
+    config::update(Section::Games, change)
+}
+
+// 游戏列表：默认游戏和配置文件中的游戏，按名称排序
+pub fn names(configs: &GameConfigs) -> Vec<String> {
+    let mut names: Vec<String> = DEFAULT_GAMES.iter().map(|game| game.to_string()).collect();
+    names.extend(configs.keys().cloned());
+    names.sort();
+    names.dedup();
+    names
+}
+
+// 在锁内启用或停用选择的游戏，只修改这些游戏，没有配置的游戏使用默认配置
+pub fn set_active(selections: &[(String, bool)]) -> Result<GameConfigs> {
+    update(|configs| {
+        for (game, active) in selections {
+            configs
+                .entry(game.clone())
+                .or_insert_with(|| GameConfig::new(game, *active))
+                .active = *active;
+        }
+        Ok(configs.clone())
+    })
+}
+
+// 从领券中心获取游戏最新的优惠券。获取期间不持有锁，获取完成后在锁内合并到最新的配置，
+// 不修改游戏的启用状态
+pub async fn fetch_coupons(
+    ctx: &RequestContext,
+    games: &[String],
+    events: &Events,
+) -> Result<GameConfigs> {
+    let configs = load()?;
+    let mut centers = HashMap::new();
+    for game in games {
+        let Some(auth_token) = read_saved_token()? else {
+            continue;
+        };
+        let community = configs
+            .get(game)
+            .map(|config| config.communityId.clone())
+            .unwrap_or_else(|| community_id(game).to_string());
+        match get_info(ctx, auth_token, community).await {
+            Ok(center) if !center.zones.is_empty() => {
+                centers.insert(game.clone(), center);
+                events
+                    .send(GrabEvent::RedPackTasksFetched {
+                        game: game.clone(),
+                        error: None,
+                    })
+                    .await;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                events
+                    .send(GrabEvent::RedPackTasksFetched {
+                        game: game.clone(),
+                        error: Some(e.to_string()),
+                    })
+                    .await;
+            }
+        }
+    }
+    if centers.is_empty() {
+        return Ok(configs);
+    }
+
+    update(|configs| {
+        for (game, center) in centers {
+            let config = configs
+                .entry(game.clone())
+                .or_insert_with(|| GameConfig::new(&game, false));
+            config.red_pack_tasks.clear();
+            config.zone_ids.clear();
+            for zone in center.zones {
+                let ids = zone.task_ids().iter().map(|id| id.to_string()).collect();
+                if let Some(id) = zone.id {
+                    config.zone_ids.insert(zone.name.clone(), id);
+                }
+                config.red_pack_tasks.insert(zone.name, ids);
+            }
+        }
+        Ok(configs.clone())
+    })
+}
+
+// 按选择启用或停用游戏并保存，启用的游戏从领券中心获取最新的优惠券
+pub async fn update_games(
+    ctx: &RequestContext,
+    selections: &[(String, bool)],
+    events: &Events,
+) -> Result<GameConfigs> {
+    let configs = set_active(selections)?;
+    let enabled: Vec<String> = selections
+        .iter()
+        .filter(|(_, active)| *active)
+        .map(|(game, _)| game.clone())
+        .collect();
+    if enabled.is_empty() {
+        return Ok(configs);
+    }
+    fetch_coupons(ctx, &enabled, events).await
+}
+
+#[test]
+fn test_zone_selection() {
+    let mut config = GameConfig::new("王者荣耀", true);
+    assert_eq!(config.communityId, "7");
+    // 没有 zoneId 时不编造
+    assert_eq!(config.zone_selection("每周专区"), None);
+
+    config.zone_ids.insert("每周专区".to_string(), 9);
+    assert_eq!(
+        config.zone_selection("每周专区"),
+        Some(ZoneSelection::Pinned(9))
+    );
+
+    // 手动指定的空列表视为未指定
+    config
+        .zone_selection
+        .insert("每周专区".to_string(), ZoneSelection::Cycle(vec![]));
+    assert_eq!(
+        config.zone_selection("每周专区"),
+        Some(ZoneSelection::Pinned(9))
+    );
+    config
+        .zone_selection
+        .insert("每周专区".to_string(), ZoneSelection::Cycle(vec![3, 4]));
+    assert_eq!(
+        config.zone_selection("每周专区"),
+        Some(ZoneSelection::Cycle(vec![3, 4]))
+    );
+}
@@ -0,0 +1,805 @@
+use crate::api::code::UnknownCodes;
+use crate::api::receive::fetch_receive;
+use crate::api::strategy::ReceiveStrategy;
+use crate::api::warmup::prewarm;
+use crate::engine::accounts::{self, TokenRecord};
+use crate::engine::events::{Events, GrabEvent, GrabStats, Ticket};
+use crate::engine::games::{self, GameConfigs, ZONES};
+use crate::engine::jobs::{finish, update_status, Job, JobStatus, Repeat};
+use crate::error::Error;
+use crate::utils::clock::{calibrate, ClockOffset, DEFAULT_SAMPLES};
+use crate::utils::request::{Headers, RequestContext};
+use crate::utils::scheduler::{wait_until, Countdown, Countdowns};
+use chrono::{Local, NaiveDateTime};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Barrier};
+
+// 运行定时任务，周期任务每次结束后顺延到下一次开抢时间继续等待
+pub async fn run_job(
+    ctx: RequestContext,
+    job: Job,
+    events: Events,
+    countdown: watch::Sender<Countdowns>,
+) {
+    let id = job.id;
+    let mut job = job;
+    loop {
+        let status = grab(&ctx, &job, &events, &countdown).await;
+        countdown.send_modify(|countdowns| {
+            countdowns.remove(&id);
+        });
+        // 任务已被删除
+        if status == JobStatus::Cancelled {
+            break;
+        }
+        // 开抢前就失败的周期任务，等本次开抢时间过后再顺延，避免反复重试
+        if job.repeat != Repeat::Once {
+            wait_until(job.target).await;
+        }
+
+        match finish(id, status, Local::now().naive_local()) {
+            Ok(Some(next)) => {
+                events
+                    .message(format!("任务#{}下次开抢时间: {}", id, next.target))
+                    .await;
+                job = next;
+            }
+            Ok(None) => break,
+            Err(e) => {
+                events
+                    .message(format!("保存任务#{}状态失败: {}", id, e))
+                    .await;
+                break;
+            }
+        }
+    }
+}
+
+// 开抢前在这些时刻发出倒计时事件（秒）
+const COUNTDOWN_MARKS: [i64; 3] = [60, 10, 3];
+// 距离第一次校准超过这个时长才重新校准，长时间等待期间本地时钟可能漂移
+const RECALIBRATE_AFTER: Duration = Duration::from_secs(300);
+// 在预热和倒计时之前多久重新校准
+const RECALIBRATE_BEFORE: Duration = Duration::from_secs(90);
+// 预热最晚在发出请求前这么久结束，留出保存任务状态的时间
+const WARMUP_MARGIN: Duration = Duration::from_secs(1);
+
+// 等待到开抢时间后抢票，返回任务结束时的状态
+pub async fn grab(
+    ctx: &RequestContext,
+    job: &Job,
+    events: &Events,
+    countdown: &watch::Sender<Countdowns>,
+) -> JobStatus {
+    let target_datetime = job.target;
+    if target_datetime <= Local::now().naive_local() {
+        return fail(
+            events,
+            job.id,
+            JobStatus::Expired,
+            "时间已经过期，请重新设置！",
+        )
+        .await;
+    }
+
+    // 任务指定账号时使用指定的账号，否则使用所有激活的账号，跳过已校验为失效的账号
+    let resolved = match accounts::resolve_all(&job.accounts) {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let reason = format!("读取账号失败: {}", e);
+            return fail(events, job.id, JobStatus::Failed(reason.clone()), &reason).await;
+        }
+    };
+    let (mut accounts, invalid): (Vec<_>, Vec<_>) = resolved
+        .into_iter()
+        .partition(|account| account.valid != Some(false));
+    for account in invalid {
+        events
+            .send(GrabEvent::AccountSkipped {
+                job: job.id,
+                account: account.mobile_phone,
+                reason: "token已失效，请重新登录".to_string(),
+            })
+            .await;
+    }
+    if accounts.is_empty() {
+        let reason = "找不到可用的账号";
+        return fail(
+            events,
+            job.id,
+            JobStatus::Failed(reason.to_string()),
+            reason,
+        )
+        .await;
+    }
+
+    // 读取抢票策略
+    let strategy = match job.strategy() {
+        Ok(strategy) => strategy,
+        Err(e) => {
+            let reason = format!("读取抢票策略失败: {}", e);
+            return fail(events, job.id, JobStatus::Failed(reason.clone()), &reason).await;
+        }
+    };
+
+    // 按服务器时钟校准发出请求的时间
+    let clock = calibrate_or(ctx, job, events, ClockOffset::default()).await;
+    let mut fire_datetime = schedule(job, &clock, &strategy, events, countdown).await;
+
+    // 等待时间较长时，在预热和倒计时之前重新校准，失败时沿用第一次的结果
+    let warmup = chrono::Duration::from_std(strategy.warmup()).unwrap_or_default();
+    let recalibrate_at =
+        fire_datetime - warmup - chrono::Duration::from_std(RECALIBRATE_BEFORE).unwrap_or_default();
+    if (recalibrate_at - Local::now().naive_local())
+        .to_std()
+        .is_ok_and(|wait| wait > RECALIBRATE_AFTER)
+    {
+        wait_until(recalibrate_at).await;
+        let clock = calibrate_or(ctx, job, events, clock).await;
+        fire_datetime = schedule(job, &clock, &strategy, events, countdown).await;
+    }
+
+    // 开抢前预热连接，同时校验账号token是否仍然有效
+    let mut warmup_at = (!strategy.warmup().is_zero()).then(|| fire_datetime - warmup);
+    for seconds in COUNTDOWN_MARKS {
+        let mark_at = fire_datetime - chrono::Duration::seconds(seconds);
+        if mark_at <= Local::now().naive_local() {
+            continue;
+        }
+        if let Some(at) = warmup_at.filter(|at| *at <= mark_at) {
+            warmup_at = None;
+            if let Err(status) = warm_up(
+                ctx,
+                job,
+                &mut accounts,
+                &strategy,
+                at,
+                fire_datetime,
+                events,
+            )
+            .await
+            {
+                return status;
+            }
+        }
+        wait_until(mark_at).await;
+        events
+            .send(GrabEvent::Countdown {
+                job: job.id,
+                seconds: seconds as u64,
+            })
+            .await;
+    }
+    if let Some(at) = warmup_at {
+        if let Err(status) = warm_up(
+            ctx,
+            job,
+            &mut accounts,
+            &strategy,
+            at,
+            fire_datetime,
+            events,
+        )
+        .await
+        {
+            return status;
+        }
+    }
+
+    // 发出请求前记录为抢票中，保存失败或等待期间任务已被删除时不发出请求
+    let saved = update_status(job.id, JobStatus::Running);
+    if !matches!(saved, Ok(true)) {
+        countdown.send_modify(|countdowns| {
+            countdowns.remove(&job.id);
+        });
+    }
+    match saved {
+        Ok(true) => {}
+        Ok(false) => {
+            events
+                .message(format!("任务#{}已被删除，不再抢票", job.id))
+                .await;
+            return JobStatus::Cancelled;
+        }
+        Err(e) => {
+            let reason = format!("保存任务状态失败: {}", e);
+            return fail(events, job.id, JobStatus::Failed(reason.clone()), &reason).await;
+        }
+    }
+    let late = wait_until(fire_datetime).await;
+    countdown.send_modify(|countdowns| {
+        countdowns.remove(&job.id);
+    });
+
+    // 开抢时读取游戏配置，等待期间修改的设置同样生效
+    let game_configs = match games::load() {
+        Ok(game_configs) => game_configs,
+        Err(e) => {
+            let reason = format!("读取游戏配置失败: {}", e);
+            return fail(events, job.id, JobStatus::Failed(reason.clone()), &reason).await;
+        }
+    };
+    fire(ctx, job, &accounts, &game_configs, &strategy, late, events).await
+}
+
+// 校准服务器时钟，失败时使用 fallback
+async fn calibrate_or(
+    ctx: &RequestContext,
+    job: &Job,
+    events: &Events,
+    fallback: ClockOffset,
+) -> ClockOffset {
+    match calibrate(ctx, DEFAULT_SAMPLES).await {
+        Ok(clock) => {
+            events
+                .send(GrabEvent::Calibrated {
+                    job: job.id,
+                    offset_ms: clock.offset.num_milliseconds(),
+                    rtt_ms: clock.rtt.num_milliseconds(),
+                    uncertainty_ms: clock.uncertainty.num_milliseconds(),
+                    samples: clock.samples,
+                })
+                .await;
+            clock
+        }
+        Err(e) => {
+            events
+                .send(GrabEvent::CalibrationFailed {
+                    job: job.id,
+                    local: fallback.samples == 0,
+                    reason: e.to_string(),
+                })
+                .await;
+            fallback
+        }
+    }
+}
+
+// 按校准结果计算本地发出请求的时间，显示在状态栏的倒计时中
+async fn schedule(
+    job: &Job,
+    clock: &ClockOffset,
+    strategy: &ReceiveStrategy,
+    events: &Events,
+    countdown: &watch::Sender<Countdowns>,
+) -> NaiveDateTime {
+    let fire_at =
+        clock.fire_at(job.target) - chrono::Duration::from_std(strategy.lead()).unwrap_or_default();
+    events
+        .send(GrabEvent::JobScheduled {
+            job: job.id,
+            target: job.target,
+            fire_at,
+        })
+        .await;
+    countdown.send_modify(|countdowns| {
+        countdowns.insert(
+            job.id,
+            Countdown {
+                target: job.target,
+                fire_at,
+                lead: strategy.lead(),
+            },
+        );
+    });
+    fire_at
+}
+
+// 整个任务失败，发出失败事件并返回任务状态
+async fn fail(events: &Events, job: u64, status: JobStatus, reason: &str) -> JobStatus {
+    events
+        .send(GrabEvent::Failed {
+            job,
+            ticket: None,
+            reason: reason.to_string(),
+        })
+        .await;
+    status
+}
+
+// 等待到预热时间后预热所有账号的连接，token已失效的账号不再参与本次抢票，
+// 全部失效时返回任务的失败状态。预热在 fire_at 之前结束，未完成校验的账号不参与本次抢票，
+// 但没有任何账号通过校验时仍然使用这些账号
+async fn warm_up(
+    ctx: &RequestContext,
+    job: &Job,
+    accounts: &mut Vec<TokenRecord>,
+    strategy: &ReceiveStrategy,
+    warmup_at: NaiveDateTime,
+    fire_at: NaiveDateTime,
+    events: &Events,
+) -> Result<(), JobStatus> {
+    wait_until(warmup_at).await;
+    let headers: Vec<Headers> = accounts.iter().map(TokenRecord::headers).collect();
+    let remaining = (fire_at - Local::now().naive_local())
+        .to_std()
+        .unwrap_or_default()
+        .saturating_sub(WARMUP_MARGIN);
+    let deadline = tokio::time::Instant::now() + remaining;
+    let results = prewarm(ctx, &headers, strategy.warm_connections, deadline).await;
+
+    let mut kept = vec![];
+    let mut unfinished = vec![];
+    let mut checked = vec![];
+    let (mut valid, mut invalid, mut unchecked) = (vec![], vec![], vec![]);
+    for (account, result) in accounts.drain(..).zip(results) {
+        let phone = account.mobile_phone.clone();
+        match result {
+            None => {
+                unfinished.push(account);
+                continue;
+            }
+            Some(Err(Error::TokenExpired)) => {
+                checked.push((phone.clone(), false));
+                invalid.push(phone);
+                continue;
+            }
+            Some(Ok(_)) => {
+                checked.push((phone.clone(), true));
+                valid.push(phone);
+            }
+            // 预热失败的账号仍然参与抢票
+            Some(Err(_)) => unchecked.push(phone),
+        }
+        kept.push(account);
+    }
+    if kept.is_empty() {
+        unchecked.extend(unfinished.iter().map(|a| a.mobile_phone.clone()));
+        kept = unfinished;
+    } else {
+        for account in unfinished {
+            events
+                .send(GrabEvent::AccountSkipped {
+                    job: job.id,
+                    account: account.mobile_phone,
+                    reason: "开抢前没有完成校验，本次不参与抢票".to_string(),
+                })
+                .await;
+        }
+    }
+    events
+        .send(GrabEvent::WarmedUp {
+            job: job.id,
+            valid,
+            invalid,
+            unchecked,
+        })
+        .await;
+    *accounts = kept;
+    if let Err(e) = accounts::record_validation(&checked) {
+        events.message(format!("保存账号校验结果失败: {}", e)).await;
+    }
+
+    if accounts.is_empty() {
+        let status = JobStatus::Failed(Error::TokenExpired.to_string());
+        let reason = "所有账号的token均已失效，已取消本次抢票，请重新登录";
+        return Err(fail(events, job.id, status, reason).await);
+    }
+    Ok(())
+}
+
+// 单张优惠券的领取结果
+enum Outcome {
+    Succeeded,
+    SoldOut,
+    Failed,
+}
+
+// 立即为每个账号领取任务中的每张优惠券，全部就绪后同时开始
+pub async fn fire(
+    ctx: &RequestContext,
+    job: &Job,
+    accounts: &[TokenRecord],
+    game_configs: &GameConfigs,
+    strategy: &ReceiveStrategy,
+    late: Duration,
+    events: &Events,
+) -> JobStatus {
+    // 任务指定的游戏，未指定时使用所有启用的游戏
+    let mut targets: Vec<_> = game_configs
+        .iter()
+        .filter(|(name, config)| {
+            if job.games.is_empty() {
+                config.active
+            } else {
+                job.games.contains(name)
+            }
+        })
+        .collect();
+    targets.sort_by_key(|(name, _)| name.as_str());
+
+    if targets.is_empty() {
+        let reason = "没有选择任何游戏";
+        return fail(
+            events,
+            job.id,
+            JobStatus::Failed(reason.to_string()),
+            reason,
+        )
+        .await;
+    }
+
+    // 任务中的专区按领券中心的顺序展开为单张优惠券，没有 zoneId 的专区无法领取，跳过
+    let mut coupons = vec![];
+    for (game_name, config) in &targets {
+        for zone in ZONES
+            .iter()
+            .filter(|zone| job.zones.iter().any(|z| z == *zone))
+        {
+            let Some(task_ids) = config.red_pack_tasks.get(*zone) else {
+                continue;
+            };
+            let Some(zones) = config.zone_selection(zone) else {
+                let reason = format!(
+                    "{} 的{}没有 zoneId，已跳过，请重新获取红包任务ID或手动指定",
+                    game_name, zone
+                );
+                events
+                    .send(GrabEvent::Failed {
+                        job: job.id,
+                        ticket: None,
+                        reason,
+                    })
+                    .await;
+                continue;
+            };
+            for task_id in task_ids {
+                coupons.push((game_name, zone, task_id, &config.communityId, zones.clone()));
+            }
+        }
+    }
+
+    if coupons.is_empty() {
+        let reason = "所选游戏和专区没有可领取的优惠券，请重新获取红包任务ID";
+        return fail(
+            events,
+            job.id,
+            JobStatus::Failed(reason.to_string()),
+            reason,
+        )
+        .await;
+    }
+
+    // 每个账号各领一份
+    let mut requests = vec![];
+    for (index, account) in accounts.iter().enumerate() {
+        let headers = account.headers();
+        for (game_name, zone, task_id, community_id, zones) in &coupons {
+            let ticket = Ticket {
+                account: account.mobile_phone.clone(),
+                game: game_name.to_string(),
+                zone: zone.to_string(),
+                task: task_id.to_string(),
+            };
+            let community_id = community_id.to_string();
+            requests.push((index, ticket, headers.clone(), community_id, zones.clone()));
+        }
+    }
+
+    events
+        .send(GrabEvent::RequestSent {
+            job: job.id,
+            count: requests.len(),
+            late_ms: late.as_secs_f64() * 1000.0,
+        })
+        .await;
+
+    // 等待所有任务就绪后同时发出
+    let unknown_codes = UnknownCodes::default();
+    let barrier = Arc::new(Barrier::new(requests.len()));
+    let mut handles = vec![];
+    for (index, ticket, headers, community_id, zones) in requests {
+        let ctx = ctx.clone();
+        let events = events.clone();
+        let barrier = barrier.clone();
+        let strategy = strategy.clone();
+        let unknown_codes = unknown_codes.clone();
+        let job = job.id;
+
+        handles.push(tokio::spawn(async move {
+            barrier.wait().await;
+
+            let task_id = ticket.task.clone();
+            let result = fetch_receive(
+                &ctx,
+                headers,
+                task_id,
+                community_id,
+                &zones,
+                &strategy,
+                &unknown_codes,
+            )
+            .await;
+            let (outcome, event) = match result {
+                Ok(_) => (Outcome::Succeeded, GrabEvent::Succeeded { job, ticket }),
+                Err(e) => {
+                    let (outcome, reason) = match e {
+                        Error::AlreadyReceived(msg) => {
+                            (Outcome::SoldOut, format!("已抢完：{}", msg))
+                        }
+                        Error::TokenExpired => {
+                            (Outcome::Failed, "账号token已失效，请重新登录".to_string())
+                        }
+                        e => (Outcome::Failed, e.to_string()),
+                    };
+                    let ticket = Some(ticket);
+                    (
+                        outcome,
+                        GrabEvent::Failed {
+                            job,
+                            ticket,
+                            reason,
+                        },
+                    )
+                }
+            };
+            events.send(event).await;
+            (index, outcome)
+        }));
+    }
+
+    // 等待所有任务完成，按账号分别统计结果
+    let mut stats: Vec<GrabStats> = accounts
+        .iter()
+        .map(|account| GrabStats {
+            account: account.mobile_phone.clone(),
+            ..Default::default()
+        })
+        .collect();
+    for handle in handles {
+        let Ok((index, outcome)) = handle.await else {
+            continue;
+        };
+        let stats = &mut stats[index];
+        stats.requested += 1;
+        match outcome {
+            Outcome::Succeeded => stats.succeeded += 1,
+            Outcome::SoldOut => stats.sold_out += 1,
+            Outcome::Failed => stats.failed += 1,
+        }
+    }
+
+    // 输出本次遇到的未收录业务码，便于补充分类
+    for (code, msg) in unknown_codes.take() {
+        events
+            .send(GrabEvent::UnknownCode {
+                job: job.id,
+                code,
+                msg,
+            })
+            .await;
+    }
+
+    // 一个请求都没有发出时不算完成
+    let requested: usize = stats.iter().map(|stats| stats.requested).sum();
+    events
+        .send(GrabEvent::JobFinished { job: job.id, stats })
+        .await;
+    if requested == 0 {
+        return JobStatus::Failed("没有发出任何领取请求".to_string());
+    }
+    JobStatus::Finished
+}
+
+#[tokio::test]
+async fn test_fire() {
+    use crate::engine::games::GameConfig;
+    use crate::utils::mock_server::{MockResponse, MockServer, COUPON_RECEIVE};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let accounts = vec![
+        TokenRecord {
+            auth_token: "token-a".to_string(),
+            mobile_phone: "13800000000".to_string(),
+            user_agent: "agent-a".to_string(),
+            active: true,
+            headers: Default::default(),
+            valid: None,
+            last_validated_at: None,
+            alias: None,
+        },
+        TokenRecord {
+            auth_token: "token-b".to_string(),
+            mobile_phone: "13900000000".to_string(),
+            user_agent: "agent-b".to_string(),
+            active: true,
+            headers: [("deviceId".to_string(), "device-b".to_string())].into(),
+            valid: Some(true),
+            last_validated_at: None,
+            alias: None,
+        },
+    ];
+    let strategy = ReceiveStrategy {
+        workers: 1,
+        window_ms: 500,
+        ..Default::default()
+    };
+
+    let mut config = GameConfig::new("三国杀", true);
+    config.red_pack_tasks.insert(
+        "每日专区".to_string(),
+        vec!["1".to_string(), "2".to_string()],
+    );
+    config
+        .red_pack_tasks
+        .insert("每周专区".to_string(), vec!["3".to_string()]);
+    config.zone_ids.insert("每日专区".to_string(), 4);
+    let mut disabled = GameConfig::new("王者荣耀", false);
+    disabled
+        .red_pack_tasks
+        .insert("每日专区".to_string(), vec!["4".to_string()]);
+    disabled.zone_ids.insert("每日专区".to_string(), 4);
+    let game_configs = GameConfigs::from([
+        ("三国杀".to_string(), config),
+        ("王者荣耀".to_string(), disabled),
+    ]);
+
+    // 每个账号都领取任务中的专区和启用的游戏
+    let job = Job::new(Local::now().naive_local(), vec!["每日专区".to_string()]);
+    let (events, mut receiver) = Events::channel(100);
+    let status = fire(
+        &ctx,
+        &job,
+        &accounts,
+        &game_configs,
+        &strategy,
+        Duration::ZERO,
+        &events,
+    )
+    .await;
+    assert_eq!(status, JobStatus::Finished);
+
+    let mut task_ids: Vec<String> = server
+        .requests_to(COUPON_RECEIVE)
+        .iter()
+        .map(|r| {
+            r.json().unwrap()["redPackTaskId"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    task_ids.sort();
+    assert_eq!(task_ids, vec!["1", "1", "2", "2"]);
+    assert!(server
+        .requests_to(COUPON_RECEIVE)
+        .iter()
+        .all(|r| r.json().unwrap()["communityId"] == "14"));
+    // 每个账号使用自己的 User-Agent 和设备请求头
+    assert!(server.requests_to(COUPON_RECEIVE).iter().all(|r| {
+        match r.header("authToken") {
+            Some("token-a") => r.header("user-agent") == Some("agent-a"),
+            Some("token-b") => {
+                r.header("user-agent") == Some("agent-b")
+                    && r.header("deviceId") == Some("device-b")
+            }
+            _ => false,
+        }
+    }));
+
+    drop(events);
+    let mut received = vec![];
+    while let Some(event) = receiver.recv().await {
+        received.push(event);
+    }
+    assert!(received.contains(&GrabEvent::RequestSent {
+        job: 0,
+        count: 4,
+        late_ms: 0.0
+    }));
+    let mut succeeded: Vec<Ticket> = received
+        .iter()
+        .filter_map(|event| match event {
+            GrabEvent::Succeeded { ticket, .. } => Some(ticket.clone()),
+            _ => None,
+        })
+        .collect();
+    succeeded.sort_by(|a, b| (&a.account, &a.task).cmp(&(&b.account, &b.task)));
+    assert_eq!(
+        succeeded[1],
+        Ticket {
+            account: "13800000000".to_string(),
+            game: "三国杀".to_string(),
+            zone: "每日专区".to_string(),
+            task: "2".to_string(),
+        }
+    );
+    assert_eq!(succeeded.len(), 4);
+    assert_eq!(
+        received.last().unwrap(),
+        &GrabEvent::JobFinished {
+            job: 0,
+            stats: accounts
+                .iter()
+                .map(|account| GrabStats {
+                    account: account.mobile_phone.clone(),
+                    requested: 2,
+                    succeeded: 2,
+                    sold_out: 0,
+                    failed: 0,
+                })
+                .collect(),
+        }
+    );
+
+    // 任务指定的游戏即使未启用也会领取，没有可用的游戏时失败
+    let (events, mut receiver) = Events::channel(100);
+    server.respond(COUPON_RECEIVE, vec![MockResponse::already_received()]);
+    let mut job = job;
+    job.games = vec!["王者荣耀".to_string()];
+    let status = fire(
+        &ctx,
+        &job,
+        &accounts[..1],
+        &game_configs,
+        &strategy,
+        Duration::ZERO,
+        &events,
+    )
+    .await;
+    assert_eq!(status, JobStatus::Finished);
+    assert_eq!(
+        server
+            .requests_to(COUPON_RECEIVE)
+            .last()
+            .unwrap()
+            .json()
+            .unwrap()["communityId"],
+        "7"
+    );
+
+    // 没有 zoneId 的专区跳过，不发出请求，任务失败
+    let sent = server.requests_to(COUPON_RECEIVE).len();
+    let mut weekly = job.clone();
+    weekly.games = vec!["三国杀".to_string()];
+    weekly.zones = vec!["每周专区".to_string()];
+    let status = fire(
+        &ctx,
+        &weekly,
+        &accounts[..1],
+        &game_configs,
+        &strategy,
+        Duration::ZERO,
+        &events,
+    )
+    .await;
+    assert!(matches!(status, JobStatus::Failed(_)));
+    assert_eq!(server.requests_to(COUPON_RECEIVE).len(), sent);
+
+    job.games = vec!["火影忍者".to_string()];
+    let status = fire(
+        &ctx,
+        &job,
+        &accounts[..1],
+        &game_configs,
+        &strategy,
+        Duration::ZERO,
+        &events,
+    )
+    .await;
+    assert!(matches!(status, JobStatus::Failed(_)));
+
+    drop(events);
+    let mut received = vec![];
+    while let Some(event) = receiver.recv().await {
+        received.push(event);
+    }
+    assert!(received.iter().any(|event| matches!(
+        event,
+        GrabEvent::JobFinished { stats, .. } if stats[0].sold_out == 1 && stats[0].succeeded == 0
+    )));
+    assert!(received.iter().any(|event| matches!(
+        event,
+        GrabEvent::Failed { ticket: None, reason, .. } if reason.contains("每周专区没有 zoneId")
+    )));
+    assert_eq!(
+        received.last().unwrap(),
+        &GrabEvent::Failed {
+            job: 0,
+            ticket: None,
+            reason: "没有选择任何游戏".to_string(),
+        }
+    );
+}
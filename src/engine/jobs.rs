@@ -0,0 +1,369 @@
+use crate::api::strategy::ReceiveStrategy;
+use crate::config::{self, Section};
+use crate::error::{Error, Result};
+use chrono::{Datelike, Local, NaiveDateTime, TimeZone, Weekday};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// 定时任务的状态
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    #[default]
+    Pending, // 等待开抢
+    Running,        // 正在抢票
+    Finished,       // 已完成
+    Failed(String), // 开抢前失败，例如token失效
+    Cancelled,      // 已取消
+    Expired,        // 程序未运行，错过了开抢时间
+}
+
+impl JobStatus {
+    pub fn label(&self) -> &str {
+        match self {
+            JobStatus::Pending => "等待中",
+            JobStatus::Running => "抢票中",
+            JobStatus::Finished => "已完成",
+            JobStatus::Failed(_) => "失败",
+            JobStatus::Cancelled => "已取消",
+            JobStatus::Expired => "已过期",
+        }
+    }
+}
+
+// 任务的重复规则
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Repeat {
+    #[default]
+    Once, // 只执行一次
+    Daily,                // 每天同一时间
+    Weekly(Vec<Weekday>), // 每周的指定几天，同一时间
+    Cron(String),         // cron 表达式：秒 分 时 日 月 周
+}
+
+const WEEKDAY_NAMES: [&str; 7] = ["一", "二", "三", "四", "五", "六", "日"];
+
+impl Repeat {
+    pub fn label(&self) -> String {
+        match self {
+            Repeat::Once => "单次".to_string(),
+            Repeat::Daily => "每天".to_string(),
+            Repeat::Weekly(days) => {
+                let days: Vec<&str> = days
+                    .iter()
+                    .map(|day| WEEKDAY_NAMES[day.num_days_from_monday() as usize])
+                    .collect();
+                format!("每周{}", days.join(","))
+            }
+            Repeat::Cron(expr) => format!("cron({})", expr),
+        }
+    }
+
+    // 检查规则是否有效
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            Repeat::Weekly(days) if days.is_empty() => {
+                Err(Error::Config("每周重复至少需要选择一天".to_string()))
+            }
+            Repeat::Cron(expr) => Schedule::from_str(expr)
+                .map(|_| ())
+                .map_err(|e| Error::Config(format!("cron表达式 \"{}\" 无效: {}", expr, e))),
+            _ => Ok(()),
+        }
+    }
+
+    // 上一次开抢时间之后的下一次开抢时间，单次任务没有下一次
+    pub fn next_after(&self, last: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Repeat::Once => None,
+            Repeat::Daily => Some(last + chrono::Duration::days(1)),
+            Repeat::Weekly(days) => (1..=7)
+                .map(|n| last + chrono::Duration::days(n))
+                .find(|next| days.contains(&next.weekday())),
+            Repeat::Cron(expr) => {
+                let schedule = Schedule::from_str(expr).ok()?;
+                let last = Local.from_local_datetime(&last).earliest()?;
+                schedule.after(&last).next().map(|next| next.naive_local())
+            }
+        }
+    }
+}
+
+// 一个定时抢票任务
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: u64,
+    pub target: NaiveDateTime, // 开抢时间（服务器时间）
+    #[serde(default)]
+    pub games: Vec<String>, // 游戏名称，为空时使用设置中启用的游戏
+    pub zones: Vec<String>,    // 专区名称
+    #[serde(default)]
+    pub accounts: Vec<String>, // 账号手机号，为空时使用所有激活的账号
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<ReceiveStrategy>, // 为空时使用全局抢票策略
+    #[serde(default)]
+    pub status: JobStatus,
+    #[serde(default)]
+    pub repeat: Repeat,
+}
+
+impl Job {
+    pub fn new(target: NaiveDateTime, zones: Vec<String>) -> Self {
+        Self {
+            id: 0,
+            target,
+            games: Vec::new(),
+            zones,
+            accounts: Vec::new(),
+            strategy: None,
+            status: JobStatus::Pending,
+            repeat: Repeat::Once,
+        }
+    }
+
+    // 从开抢时间开始的接下来 n 次开抢时间
+    pub fn occurrences(&self, n: usize) -> Vec<NaiveDateTime> {
+        std::iter::successors(Some(self.target), |last| self.repeat.next_after(*last))
+            .take(n)
+            .collect()
+    }
+
+    // 开抢时间已过的周期任务顺延到 now 之后，单次任务返回 false
+    pub fn rearm(&mut self, now: NaiveDateTime) -> bool {
+        if self.repeat == Repeat::Once {
+            return false;
+        }
+        let mut next = self.target;
+        while next <= now {
+            match self.repeat.next_after(next) {
+                Some(time) => next = time,
+                None => return false,
+            }
+        }
+        self.target = next;
+        self.status = JobStatus::Pending;
+        true
+    }
+
+    // 本次执行结束后，周期任务移动到下一次开抢时间
+    pub fn advance(&mut self, now: NaiveDateTime) -> bool {
+        match self.repeat.next_after(self.target) {
+            Some(next) => {
+                self.target = next;
+                self.rearm(now)
+            }
+            None => false,
+        }
+    }
+
+    // 修改重复规则，cron 任务的开抢时间改为 now 之后的第一次
+    pub fn set_repeat(&mut self, repeat: Repeat, now: NaiveDateTime) -> Result<()> {
+        repeat.validate()?;
+        if let Repeat::Cron(_) = repeat {
+            let start = now.min(self.target) - chrono::Duration::seconds(1);
+            self.target = repeat
+                .next_after(start)
+                .ok_or_else(|| Error::Config("cron表达式没有后续的执行时间".to_string()))?;
+        }
+        self.repeat = repeat;
+        self.rearm(now);
+        Ok(())
+    }
+
+    // 任务使用的抢票策略
+    pub fn strategy(&self) -> Result<ReceiveStrategy> {
+        match &self.strategy {
+            Some(strategy) => Ok(strategy.clone()),
+            None => ReceiveStrategy::load(),
+        }
+    }
+
+    // 列表中显示的描述
+    pub fn describe(&self) -> String {
+        let games = if self.games.is_empty() {
+            "已启用的游戏".to_string()
+        } else {
+            self.games.join(",")
+        };
+        format!(
+            "#{} {} {} {} {} [{}]",
+            self.id,
+            self.target.format("%Y-%m-%d %H:%M:%S"),
+            self.repeat.label(),
+            games,
+            self.zones.join(","),
+            self.status.label()
+        )
+    }
+}
+
+// 定时任务列表，保存在配置文件的 jobs 节
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobList {
+    pub jobs: Vec<Job>,
+}
+
+impl JobList {
+    // 从配置文件读取任务列表，格式错误时返回出错的字段
+    pub fn load() -> Result<Self> {
+        Ok(Self {
+            jobs: config::load(Section::Jobs)?,
+        })
+    }
+
+    // 读取、修改并保存任务列表，期间一直持有锁，界面、后台模式和命令行不会覆盖对方的修改
+    pub fn update<R>(change: impl FnOnce(&mut Self) -> Result<R>) -> Result<R> {
+        config::update(Section::Jobs, |jobs: &mut Vec<Job>| {
+            let mut list = Self {
+                jobs: std::mem::take(jobs),
+            };
+            let result = change(&mut list);
+            *jobs = list.jobs;
+            result
+        })
+    }
+
+    // 添加任务并分配编号
+    pub fn add(&mut self, mut job: Job) -> u64 {
+        job.id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        let id = job.id;
+        self.jobs.push(job);
+        self.jobs.sort_by_key(|job| job.target);
+        id
+    }
+
+    pub fn remove(&mut self, id: u64) -> Option<Job> {
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        Some(self.jobs.remove(index))
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    pub fn set_status(&mut self, id: u64, status: JobStatus) -> bool {
+        match self.jobs.iter_mut().find(|job| job.id == id) {
+            Some(job) => {
+                job.status = status;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // 等待中但开抢时间已过的任务：周期任务顺延到下一次，其余标记为已过期
+    // 返回需要等待开抢的任务
+    pub fn arm(&mut self, now: NaiveDateTime) -> Vec<Job> {
+        let mut pending = Vec::new();
+        for job in &mut self.jobs {
+            if job.status == JobStatus::Cancelled {
+                continue;
+            }
+            if (job.status == JobStatus::Pending && job.target > now) || job.rearm(now) {
+                pending.push(job.clone());
+            } else if matches!(job.status, JobStatus::Pending | JobStatus::Running) {
+                job.status = JobStatus::Expired;
+            }
+        }
+        self.jobs.sort_by_key(|job| job.target);
+        pending
+    }
+}
+
+// 任务结束后记录状态，周期任务顺延到下一次并返回，需要继续等待
+pub fn finish(id: u64, status: JobStatus, now: NaiveDateTime) -> Result<Option<Job>> {
+    JobList::update(|list| {
+        let Some(job) = list.jobs.iter_mut().find(|job| job.id == id) else {
+            return Ok(None);
+        };
+        job.status = status;
+        let next = job.advance(now).then(|| job.clone());
+        list.jobs.sort_by_key(|job| job.target);
+        Ok(next)
+    })
+}
+
+// 更新配置文件中某个任务的状态，任务已被删除时返回 false
+pub fn update_status(id: u64, status: JobStatus) -> Result<bool> {
+    JobList::update(|list| Ok(list.set_status(id, status)))
+}
+
+#[test]
+fn test_job_list() {
+    let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+
+    let mut list = JobList::default();
+    let late = list.add(Job::new(at("2026-10-20 10:00:00"), vec!["每周专区".into()]));
+    let early = list.add(Job::new(at("2026-10-19 10:00:00"), vec!["每日专区".into()]));
+    let past = list.add(Job::new(at("2026-10-17 10:00:00"), vec!["每日专区".into()]));
+    assert_eq!((late, early, past), (1, 2, 3));
+    // 按开抢时间排序
+    let ids: Vec<u64> = list.jobs.iter().map(|job| job.id).collect();
+    assert_eq!(ids, vec![3, 2, 1]);
+
+    // 过期的任务不再启动
+    let pending = list.arm(at("2026-10-18 12:00:00"));
+    assert_eq!(pending.len(), 2);
+    assert_eq!(list.get(past).unwrap().status, JobStatus::Expired);
+
+    assert!(list.set_status(early, JobStatus::Finished));
+    assert_eq!(list.remove(late).unwrap().id, late);
+    // 新编号在现有最大编号上递增
+    assert_eq!(list.add(Job::new(at("2026-10-21 10:00:00"), vec![])), 4);
+
+    // 旧版本没有的字段使用默认值
+    let job: Job = serde_json::from_str(
+        r#"{ "id": 7, "target": "2026-10-19T10:00:00", "zones": ["每日专区"] }"#,
+    )
+    .unwrap();
+    assert_eq!(job.status, JobStatus::Pending);
+    assert!(job.games.is_empty() && job.strategy.is_none());
+    assert_eq!(job.repeat, Repeat::Once);
+}
+
+#[test]
+fn test_repeat() {
+    let at = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap();
+
+    // 2026-10-19 是周一
+    let mut job = Job::new(at("2026-10-19 10:00:00"), vec!["每日专区".into()]);
+    assert_eq!(job.occurrences(3), vec![at("2026-10-19 10:00:00")]);
+    assert!(!job.rearm(at("2026-10-20 00:00:00")));
+    assert!(!job.advance(at("2026-10-18 00:00:00")));
+
+    job.repeat = Repeat::Daily;
+    assert_eq!(job.occurrences(2)[1], at("2026-10-20 10:00:00"));
+    // 错过的开抢时间顺延到下一次
+    assert!(job.rearm(at("2026-10-21 12:00:00")));
+    assert_eq!(job.target, at("2026-10-22 10:00:00"));
+    // 提前结束的任务也会移动到下一次，不会立即重新执行
+    assert!(job.advance(at("2026-10-21 12:00:00")));
+    assert_eq!(job.target, at("2026-10-23 10:00:00"));
+
+    job.repeat = Repeat::Weekly(vec![Weekday::Mon, Weekday::Thu]);
+    job.target = at("2026-10-22 10:00:00");
+    assert_eq!(
+        job.occurrences(3),
+        vec![
+            at("2026-10-22 10:00:00"),
+            at("2026-10-26 10:00:00"),
+            at("2026-10-29 10:00:00"),
+        ]
+    );
+    assert_eq!(job.repeat.label(), "每周一,四");
+
+    // cron 任务的开抢时间由表达式决定
+    let now = at("2026-10-19 09:00:00");
+    job.set_repeat(Repeat::Cron("0 30 9 * * Mon-Fri".into()), now)
+        .unwrap();
+    assert_eq!(
+        job.occurrences(2),
+        vec![at("2026-10-19 09:30:00"), at("2026-10-20 09:30:00")]
+    );
+    assert!(matches!(
+        job.set_repeat(Repeat::Cron("每天十点".into()), now),
+        Err(Error::Config(_))
+    ));
+    assert!(Repeat::Weekly(vec![]).validate().is_err());
+}
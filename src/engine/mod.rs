@@ -0,0 +1,10 @@
+// 抢票引擎：账号、游戏、定时任务和抢票流程，不依赖界面
+//
+// 进度通过事件发出，界面、命令行和后台模式只负责展示。
+pub mod accounts;
+pub mod events;
+pub mod games;
+pub mod grab;
+pub mod jobs;
+pub mod runner;
+pub mod transfer;
@@ -0,0 +1,106 @@
+// 按配置文件中的任务列表运行定时任务
+//
+// 界面和后台模式同时运行时只由先启动的一方执行定时任务，避免同一个任务开抢两次。
+// 执行定时任务的一方定期调用 sync 重新读取任务列表，启动新增或修改过的任务，
+// 取消已删除的任务，命令行或另一端修改的任务在下一次同步后生效。
+use crate::config;
+use crate::engine::events::Events;
+use crate::engine::grab::run_job;
+use crate::engine::jobs::{Job, JobList, JobStatus};
+use crate::error::{Error, Result};
+use crate::utils::persist::FileLock;
+use crate::utils::request::RequestContext;
+use crate::utils::scheduler::Countdowns;
+use chrono::Local;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+
+// 重新读取任务列表的间隔
+pub const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct JobRunner {
+    ctx: RequestContext,
+    events: Events,
+    countdown: watch::Sender<Countdowns>,
+    running: HashMap<u64, (Job, JoinHandle<()>)>, // 已启动的任务和启动时的内容
+    _lock: FileLock,                              // 执行定时任务的锁，运行期间一直持有
+}
+
+impl JobRunner {
+    // 获取执行定时任务的锁，其他进程正在执行定时任务时返回 None
+    pub fn claim(
+        ctx: RequestContext,
+        events: Events,
+        countdown: watch::Sender<Countdowns>,
+    ) -> Result<Option<Self>> {
+        let dir = config::dir().ok_or_else(|| Error::Config("无法获取配置目录".to_string()))?;
+        let Some(lock) = FileLock::try_acquire(&dir.join("runner"))? else {
+            return Ok(None);
+        };
+        Ok(Some(Self {
+            ctx,
+            events,
+            countdown,
+            running: HashMap::new(),
+            _lock: lock,
+        }))
+    }
+
+    // 按任务列表启动新增或修改过的任务，取消已删除的任务，返回本次启动的任务。
+    // 读取失败时保留正在运行的任务，等修正配置文件后的下一次同步
+    pub fn sync(&mut self) -> Result<Vec<Job>> {
+        let jobs = JobList::load()?;
+        let now = Local::now().naive_local();
+
+        let mut removed = vec![];
+        self.running.retain(|id, (_, handle)| {
+            let keep = !handle.is_finished() && jobs.get(*id).is_some();
+            if !keep {
+                handle.abort();
+                removed.push(*id);
+            }
+            keep
+        });
+        for id in removed {
+            self.remove_countdown(id);
+        }
+
+        let mut started = vec![];
+        for job in jobs.jobs {
+            if job.status != JobStatus::Pending || job.target <= now {
+                continue;
+            }
+            match self.running.get(&job.id) {
+                Some((spawned, _)) if *spawned == job => continue,
+                Some((_, handle)) => handle.abort(),
+                None => {}
+            }
+            let handle = tokio::spawn(run_job(
+                self.ctx.clone(),
+                job.clone(),
+                self.events.clone(),
+                self.countdown.clone(),
+            ));
+            self.running.insert(job.id, (job.clone(), handle));
+            started.push(job);
+        }
+        Ok(started)
+    }
+
+    fn remove_countdown(&self, id: u64) {
+        self.countdown.send_modify(|countdowns| {
+            countdowns.remove(&id);
+        });
+    }
+}
+
+// 退出时取消所有等待中的任务
+impl Drop for JobRunner {
+    fn drop(&mut self) {
+        for (_, (_, handle)) in self.running.drain() {
+            handle.abort();
+        }
+    }
+}
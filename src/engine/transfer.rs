@@ -0,0 +1,299 @@
+// 账号批量导入导出的文件格式
+//
+// 支持 CSV、JSON 和文本三种格式。文本格式是从网页复制的
+// `AuthToken:... MobilePhone:...` 段落，也可以每行一个token，只包含token和手机号。
+// CSV 的 headers 列是 JSON 对象，保存账号的其他请求头。
+use crate::api::queryMobilePhone::TokenRecord;
+use crate::error::{Error, Result};
+use crate::utils::request::Headers;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const CSV_COLUMNS: [&str; 6] = [
+    "auth_token",
+    "mobile_phone",
+    "user_agent",
+    "alias",
+    "active",
+    "headers",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Json,
+    Text,
+}
+
+impl Format {
+    // 导出后能否完整还原账号
+    pub fn is_lossless(self) -> bool {
+        self != Format::Text
+    }
+
+    // 按扩展名判断格式，其他扩展名按文本处理
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("csv") => Format::Csv,
+            Some("json") => Format::Json,
+            _ => Format::Text,
+        }
+    }
+}
+
+// 文件中的一个账号，手机号为空时导入时查询
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AccountEntry {
+    pub auth_token: String,
+    #[serde(default)]
+    pub mobile_phone: Option<String>,
+    #[serde(default)]
+    pub user_agent: String,
+    #[serde(default)]
+    pub alias: Option<String>,
+    #[serde(default)]
+    pub active: Option<bool>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+impl AccountEntry {
+    // 导出时 redact 为真则隐藏token中间部分
+    pub fn from_record(record: &TokenRecord, redact: bool) -> Self {
+        Self {
+            auth_token: if redact {
+                redact_token(&record.auth_token)
+            } else {
+                record.auth_token.clone()
+            },
+            mobile_phone: Some(record.mobile_phone.clone()),
+            user_agent: record.user_agent.clone(),
+            alias: record.alias.clone(),
+            active: Some(record.active),
+            headers: record.headers.clone(),
+        }
+    }
+
+    // 导出时隐藏过的token不能再导入
+    pub fn is_redacted(&self) -> bool {
+        self.auth_token.contains('*')
+    }
+}
+
+pub fn redact_token(token: &str) -> String {
+    let chars: Vec<char> = token.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}****{}", head, tail)
+}
+
+// JSON 可以是账号数组，也可以是未加密的账号配置（配置文件的 accounts 节或旧版本的 auth_token.json）
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonAccounts {
+    List(Vec<AccountEntry>),
+    Storage { records: Vec<AccountEntry> },
+}
+
+pub fn parse(content: &str, format: Format) -> Result<Vec<AccountEntry>> {
+    let entries = match format {
+        Format::Json => match serde_json::from_str(content)
+            .map_err(|e| Error::Config(format!("JSON格式错误: {}", e)))?
+        {
+            JsonAccounts::List(entries) => entries,
+            JsonAccounts::Storage { records } => records,
+        },
+        Format::Csv => parse_csv(content)?,
+        Format::Text => parse_text(content),
+    };
+    Ok(entries
+        .into_iter()
+        .map(|mut entry| {
+            entry.auth_token = Headers::token(entry.auth_token).auth_token;
+            entry
+        })
+        .filter(|entry| !entry.auth_token.is_empty())
+        .collect())
+}
+
+// 第一行是表头，列的顺序不限，只有 auth_token 列必填
+fn parse_csv(content: &str) -> Result<Vec<AccountEntry>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let header = reader
+        .headers()
+        .map_err(|e| Error::Config(format!("CSV格式错误: {}", e)))?
+        .clone();
+    let column = |name: &str| header.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let token_column =
+        column("auth_token").ok_or_else(|| Error::Config("CSV缺少 auth_token 列".to_string()))?;
+    let [_, phone_column, ua_column, alias_column, active_column, headers_column] =
+        CSV_COLUMNS.map(column);
+
+    let mut entries = vec![];
+    for (line, row) in reader.records().enumerate() {
+        let row = row.map_err(|e| Error::Config(format!("CSV格式错误: {}", e)))?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|c| row.get(c))
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        let active = match field(active_column).as_deref() {
+            None => None,
+            Some("true") | Some("1") => Some(true),
+            Some("false") | Some("0") => Some(false),
+            Some(value) => {
+                return Err(Error::Config(format!(
+                    "CSV第{}行 active 列的值 {} 无效",
+                    line + 2,
+                    value
+                )))
+            }
+        };
+        let headers = match field(headers_column) {
+            None => BTreeMap::new(),
+            Some(value) => serde_json::from_str(&value)
+                .map_err(|e| Error::Config(format!("CSV第{}行 headers 列无效: {}", line + 2, e)))?,
+        };
+        entries.push(AccountEntry {
+            auth_token: field(Some(token_column)).unwrap_or_default(),
+            mobile_phone: field(phone_column),
+            user_agent: field(ua_column).unwrap_or_default(),
+            alias: field(alias_column),
+            active,
+            headers,
+        });
+    }
+    Ok(entries)
+}
+
+// 按 AuthToken: 分段，没有 AuthToken: 时每行一个token
+fn parse_text(content: &str) -> Vec<AccountEntry> {
+    let blocks: Vec<&str> = if content.contains("AuthToken:") {
+        content.split("AuthToken:").skip(1).collect()
+    } else {
+        content.lines().collect()
+    };
+    blocks
+        .into_iter()
+        .map(|block| {
+            let mut parts = block.splitn(2, "MobilePhone:");
+            let auth_token: String = parts
+                .next()
+                .unwrap_or_default()
+                .split_whitespace()
+                .collect();
+            let mobile_phone = parts
+                .next()
+                .and_then(|rest| rest.split_whitespace().next())
+                .map(str::to_string);
+            AccountEntry {
+                auth_token,
+                mobile_phone,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+pub fn format(entries: &[AccountEntry], format: Format) -> Result<String> {
+    match format {
+        Format::Json => Ok(serde_json::to_string_pretty(entries)?),
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(vec![]);
+            let write_error = |e: csv::Error| Error::Config(format!("写入CSV失败: {}", e));
+            writer.write_record(CSV_COLUMNS).map_err(write_error)?;
+            for entry in entries {
+                let headers = if entry.headers.is_empty() {
+                    String::new()
+                } else {
+                    serde_json::to_string(&entry.headers)?
+                };
+                writer
+                    .write_record([
+                        entry.auth_token.as_str(),
+                        entry.mobile_phone.as_deref().unwrap_or_default(),
+                        entry.user_agent.as_str(),
+                        entry.alias.as_deref().unwrap_or_default(),
+                        &entry.active.map(|a| a.to_string()).unwrap_or_default(),
+                        &headers,
+                    ])
+                    .map_err(write_error)?;
+            }
+            let bytes = writer
+                .into_inner()
+                .map_err(|e| Error::Config(format!("写入CSV失败: {}", e)))?;
+            Ok(String::from_utf8_lossy(&bytes).into_owned())
+        }
+        Format::Text => Ok(entries
+            .iter()
+            .map(|entry| {
+                format!(
+                    "AuthToken:{} MobilePhone:{}\n",
+                    entry.auth_token,
+                    entry.mobile_phone.as_deref().unwrap_or_default()
+                )
+            })
+            .collect()),
+    }
+}
+
+#[test]
+fn test_parse_and_format() {
+    let text =
+        "AuthToken:abc\ndef\nMobilePhone:13800000000\n\nAuthToken: ghi MobilePhone:13900000000";
+    let entries = parse(text, Format::Text).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].auth_token, "abcdef");
+    assert_eq!(entries[0].mobile_phone.as_deref(), Some("13800000000"));
+    assert_eq!(entries[1].auth_token, "ghi");
+    assert_eq!(parse("tok-1\n\ntok-2\n", Format::Text).unwrap().len(), 2);
+
+    // 导出后再导入内容不变
+    let entries = vec![AccountEntry {
+        auth_token: "token-a".to_string(),
+        mobile_phone: Some("13800000000".to_string()),
+        user_agent: "ua, with comma".to_string(),
+        alias: Some("主号".to_string()),
+        active: Some(true),
+        headers: [("deviceId".to_string(), "device, \"a\"".to_string())].into(),
+    }];
+    for f in [Format::Csv, Format::Json] {
+        assert_eq!(parse(&format(&entries, f).unwrap(), f).unwrap(), entries);
+    }
+    let storage =
+        r#"{"records":[{"auth_token":"t","mobile_phone":"1","user_agent":"u","active":false}]}"#;
+    assert_eq!(parse(storage, Format::Json).unwrap()[0].active, Some(false));
+
+    assert!(parse("mobile_phone\n138", Format::Csv).is_err());
+    assert!(parse("auth_token,active\nt,maybe", Format::Csv).is_err());
+    assert!(parse("auth_token,headers\nt,deviceId", Format::Csv).is_err());
+    assert!(!Format::Text.is_lossless());
+    assert_eq!(redact_token("0123456789abcdef"), "0123****cdef");
+    assert!(AccountEntry::from_record(
+        &TokenRecord {
+            auth_token: "0123456789abcdef".to_string(),
+            mobile_phone: "1".to_string(),
+            user_agent: String::new(),
+            active: true,
+            headers: BTreeMap::new(),
+            valid: None,
+            last_validated_at: None,
+            alias: None,
+        },
+        true
+    )
+    .is_redacted());
+}
@@ -0,0 +1,157 @@
+use crate::api::code::{self, Outcome};
+use crate::config::Problem;
+use std::fmt;
+
+// 已领取或已抢完
+pub const CODE_ALREADY_RECEIVED: &str = "2040";
+
+// 统一错误类型，区分不同的失败原因，方便抢票逻辑和界面分别处理
+#[derive(Debug)]
+pub enum Error {
+    Transport(reqwest::Error),              // 网络传输失败
+    Timeout,                                // 请求超时
+    InvalidResponse(String),                // 响应不是预期的JSON，保存原始响应体
+    TokenExpired,                           // authToken 错误或已过期
+    AlreadyReceived(String),                // 2040: 已领取或已抢完
+    Rejected { code: String, msg: String }, // 其他不可重试的业务失败
+    RateLimited(String),                    // 请求过于频繁
+    RiskControl(String),                    // 触发风控
+    Business { code: String, msg: String }, // 可重试或未收录的业务码
+    Io(std::io::Error),                     // 读写配置文件失败
+    Json(serde_json::Error),                // 序列化配置失败
+    Config(String),                         // 配置内容不合法
+    Invalid(Problem),                       // 配置文件的某个字段格式错误
+    Locked,                                 // 账号文件已加密，尚未解锁
+    Decrypt,                                // 口令错误或密文已损坏
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    // 根据业务码构造错误
+    pub fn business(code: impl Into<String>, msg: impl Into<String>) -> Self {
+        let code = code.into();
+        let msg = msg.into();
+        if code == CODE_ALREADY_RECEIVED {
+            return Error::AlreadyReceived(msg);
+        }
+        match code::classify(&code) {
+            Outcome::PermanentFailure => Error::Rejected { code, msg },
+            Outcome::RetryLater => Error::RateLimited(msg),
+            Outcome::AuthFailure => Error::TokenExpired,
+            Outcome::RiskControl => Error::RiskControl(msg),
+            _ => Error::Business { code, msg },
+        }
+    }
+
+    // 错误对应的处理方式
+    pub fn outcome(&self) -> Outcome {
+        match self {
+            Error::Transport(_) | Error::Timeout | Error::InvalidResponse(_) => Outcome::RetryNow,
+            Error::TokenExpired => Outcome::AuthFailure,
+            Error::AlreadyReceived(_) | Error::Rejected { .. } => Outcome::PermanentFailure,
+            Error::RateLimited(_) => Outcome::RetryLater,
+            Error::RiskControl(_) => Outcome::RiskControl,
+            Error::Business { code, .. } => code::classify(code),
+            Error::Io(_)
+            | Error::Json(_)
+            | Error::Config(_)
+            | Error::Invalid(_)
+            | Error::Locked
+            | Error::Decrypt => Outcome::PermanentFailure,
+        }
+    }
+
+    // 是否值得在抢票窗口内重试
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.outcome(),
+            Outcome::RetryNow | Outcome::RetryLater | Outcome::Unknown
+        )
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            Error::Io(e) => Some(e),
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Transport(e) => write!(f, "网络请求失败: {}", e),
+            Error::Timeout => write!(f, "请求超时"),
+            Error::InvalidResponse(_) => write!(f, "响应格式错误"),
+            Error::TokenExpired => write!(f, "authToken输入错误或已过期"),
+            Error::AlreadyReceived(msg) => write!(f, "{}", msg),
+            Error::Rejected { code, msg } => write!(f, "{}({})", msg, code),
+            Error::RateLimited(msg) => write!(f, "请求过于频繁: {}", msg),
+            Error::RiskControl(msg) => write!(f, "触发风控: {}", msg),
+            Error::Business { code, msg } => write!(f, "{}({})", msg, code),
+            Error::Io(e) => write!(f, "读写文件失败: {}", e),
+            Error::Json(e) => write!(f, "序列化失败: {}", e),
+            Error::Config(msg) => write!(f, "配置错误: {}", msg),
+            Error::Invalid(problem) => write!(f, "配置错误: {}", problem),
+            Error::Locked => write!(f, "账号文件已加密，请先输入口令解锁"),
+            Error::Decrypt => write!(f, "口令错误或账号文件已损坏"),
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            Error::Timeout
+        } else {
+            Error::Transport(e)
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+#[test]
+fn test_business_error() {
+    assert!(matches!(
+        Error::business("2040", "优惠券已抢完"),
+        Error::AlreadyReceived(msg) if msg == "优惠券已抢完"
+    ));
+    let error = Error::business("2030", "活动未开始");
+    assert!(error.is_retryable());
+    assert_eq!(error.to_string(), "活动未开始(2030)");
+    assert!(!Error::TokenExpired.is_retryable());
+
+    assert!(matches!(
+        Error::business("2041", "已达上限"),
+        Error::Rejected { .. }
+    ));
+    assert!(matches!(
+        Error::business("9001", "太频繁"),
+        Error::RateLimited(_)
+    ));
+    assert!(matches!(
+        Error::business("1001", "请先登录"),
+        Error::TokenExpired
+    ));
+    assert!(matches!(
+        Error::business("9002", "风险账号"),
+        Error::RiskControl(_)
+    ));
+    assert_eq!(Error::business("8888", "未知").outcome(), Outcome::Unknown);
+}
@@ -1,2 +1,8 @@
+// 接口字段沿用服务端的驼峰命名
+#![allow(non_snake_case, non_upper_case_globals)]
+
 pub mod api;
-pub mod utils;
\ No newline at end of file
+pub mod config;
+pub mod engine;
+pub mod error;
+pub mod utils;
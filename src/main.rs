@@ -1,15 +1,28 @@
+#![allow(non_snake_case, non_camel_case_types)]
+#![allow(clippy::collapsible_match, clippy::module_inception)]
+
 use color_eyre::Result;
 use crossterm::event::KeyEventKind;
 use crossterm::event::{self, Event, KeyCode};
 use ratatui::DefaultTerminal;
 
 mod Gui;
+mod cli;
+mod daemon;
+use clap::Parser;
 use Gui::timed_ticket_grabbing::TimedGrabbingState;
 use Gui::Gui::handle_key_input_main;
 use Gui::Gui::render_gui;
 use Gui::Login::handle_login_input;
 use Gui::Setting::SettingState;
 use UTermux::api;
+use UTermux::config;
+use UTermux::engine::accounts;
+use UTermux::engine::events::{Events, GrabEvent};
+use UTermux::engine::jobs::JobList;
+use UTermux::engine::runner::{JobRunner, RELOAD_INTERVAL};
+use UTermux::utils::request::{RequestConfig, RequestContext};
+use UTermux::utils::scheduler::Countdowns;
 // 主界面状态
 pub struct GuiState {
     pub selected_index: usize,       // 当前选中的功能列表项
@@ -20,14 +33,17 @@ pub struct GuiState {
     pub active_input: usize,        // 新增：当前激活的输入框（0: AuthToken, 1: UserAgent）
     pub last_key: KeyCode,          // 新增：存储最后按下的键
     pub console_info: String,        // 控制台输出信息
-    pub console_sender: tokio::sync::mpsc::Sender<String>, // 明确使用完整路径
-    pub console_receiver: tokio::sync::mpsc::Receiver<String>, // 明确使用完整路径
+    pub console_sender: Events,      // 抢票引擎的事件发送端
+    pub console_receiver: tokio::sync::mpsc::Receiver<GrabEvent>, // 明确使用完整路径
     pub console_scroll: usize,       // 控制台滚动条位置
     pub max_console_lines: usize,    // 控制台最大行数
     pub auto_scroll: bool,           // 自动滚动
     pub setting_index: usize,        // 设置页面索引
     pub show_prop: bool,             // 是否显示弹窗
     pub setting_state: SettingState, // 设置页面状态
+    pub request_context: RequestContext, // 共享的HTTP请求上下文
+    pub countdown: tokio::sync::watch::Sender<Countdowns>, // 定时任务倒计时，显示在状态栏
+    pub job_runner: Option<JobRunner>, // 运行定时任务，后台模式正在运行时为 None
 }
 
 // 定时抢票界面状态
@@ -64,15 +80,50 @@ pub enum ticket_list {
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
+
+    // 带子命令时只执行命令，不启动界面
+    let cli = cli::Cli::parse();
+
+    // 合并旧版本分散保存的配置文件，升级旧版本的配置格式
+    let notice = match config::init() {
+        Ok(notice) => notice,
+        Err(e) => Some(format!("配置文件迁移失败: {}，请运行 UTermux config check", e)),
+    };
+    if cli.daemon || cli.command.is_some() {
+        if let Some(notice) = &notice {
+            eprintln!("{}", notice);
+        }
+    }
+
+    if cli.daemon {
+        cli::unlock_accounts()?;
+        return daemon::run(cli.log).await;
+    }
+    if let Some(command) = cli.command {
+        if let Err(e) = cli::execute(command, cli.json).await {
+            cli::report_error(&e, cli.json);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let terminal = ratatui::init();
-    let result = run(terminal).await;
+    let result = run(terminal, notice).await;
     ratatui::restore();
     result
 }
 
-async fn run(mut terminal: DefaultTerminal) -> Result<()> {
+async fn run(mut terminal: DefaultTerminal, notice: Option<String>) -> Result<()> {
+    // 账号文件加密时先输入口令
+    if accounts::is_locked() && !Gui::Unlock::unlock(&mut terminal)? {
+        return Ok(());
+    }
+
     // 使用 tokio 的通道
-    let (sender, receiver) = tokio::sync::mpsc::channel(100); // 设置缓冲区大小为100
+    let (sender, receiver) = Events::channel(100); // 设置缓冲区大小为100
+
+    // 整个会话共享同一个HTTP客户端，避免每次请求重新握手
+    let request_context = RequestContext::new(RequestConfig::from_env())?;
 
     // 主界面状态
     let mut state = GuiState {
@@ -92,10 +143,57 @@ async fn run(mut terminal: DefaultTerminal) -> Result<()> {
         setting_index: 0,                  // 设置页面索引
         show_prop: false,                  // 是否显示弹窗
         setting_state: SettingState::default(),
+        request_context,
+        countdown: tokio::sync::watch::Sender::new(Countdowns::new()),
+        job_runner: None,
     };
 
+    // 配置文件迁移的结果和配置中的问题
+    if let Some(notice) = notice {
+        state.add_console_message(notice);
+    }
+    for error in std::mem::take(&mut state.setting_state.load_errors) {
+        state.add_console_message(error);
+    }
+    if let Ok(problems) = config::check() {
+        for problem in &problems {
+            state.add_console_message(format!("配置有误 {}", problem));
+        }
+    }
+
+    // 后台模式正在运行时定时任务由后台模式执行
+    let runner = JobRunner::claim(
+        state.request_context.clone(),
+        state.console_sender.clone(),
+        state.countdown.clone(),
+    );
+    match runner {
+        Ok(Some(runner)) => state.job_runner = Some(runner),
+        Ok(None) => state.add_console_message("后台模式正在运行，定时任务由后台模式执行".to_string()),
+        Err(e) => state.add_console_message(format!("无法确认后台模式是否在运行，不执行定时任务: {}", e)),
+    }
+
+    // 启动配置文件中等待开抢的定时任务，错过时间的任务标记为已过期
+    if state.job_runner.is_some() {
+        if let Err(e) = JobList::update(|jobs| Ok(jobs.arm(chrono::Local::now().naive_local()))) {
+            state.add_console_message(format!("保存定时任务失败: {}", e));
+        }
+        Gui::start_grabbing_tickets::sync_jobs(&mut state);
+    }
+    let mut jobs_synced_at = std::time::Instant::now();
+
+    // 定期校验已保存账号的token
+    tokio::spawn(accounts::validate_periodically(
+        state.request_context.clone(),
+        accounts::VALIDATE_INTERVAL,
+        state.console_sender.clone(),
+    ));
+
     // 将TimedGrabbingState移到这里，作为持久化状态
     let mut timed_grabbing_state = TimedGrabbingState::default();
+    if let Some(e) = &timed_grabbing_state.jobs_error {
+        state.add_console_message(format!("读取定时任务失败: {}", e));
+    }
 
     // 定时抢票界面状态
     let mut timed_ticket_grabbing_state = timed_ticket_grabbing_state {
@@ -227,10 +325,6 @@ async fn run(mut terminal: DefaultTerminal) -> Result<()> {
                                         &mut timed_grabbing_state,
                                         key.code,
                                     );
-
-                                    if key.code == KeyCode::Esc {
-                                        state.current_page = function_list::Main;
-                                    }
                                 }
                                 function_list::Setting => {
                                     SettingState::setting_handle_key(&mut state, key.code);
@@ -242,9 +336,27 @@ async fn run(mut terminal: DefaultTerminal) -> Result<()> {
             }
         }
 
+        // 定期重新读取任务列表，命令行新增、删除的任务同样生效
+        if jobs_synced_at.elapsed() >= RELOAD_INTERVAL {
+            Gui::start_grabbing_tickets::sync_jobs(&mut state);
+            jobs_synced_at = std::time::Instant::now();
+        }
+
         // 检查是否有新的控制台消息
-        if let Ok(msg) = state.console_receiver.try_recv() {
-            state.add_console_message(msg);
+        if let Ok(event) = state.console_receiver.try_recv() {
+            // 校验结果同步到账号管理弹窗
+            if let GrabEvent::AccountChecked { account, valid } = &event {
+                if let Some(record) = state
+                    .setting_state
+                    .accounts
+                    .iter_mut()
+                    .find(|r| &r.mobile_phone == account)
+                {
+                    record.valid = Some(*valid);
+                    record.last_validated_at = Some(chrono::Local::now().naive_local());
+                }
+            }
+            state.add_console_event(&event);
         }
 
         // 渲染界面
@@ -273,6 +385,16 @@ async fn run(mut terminal: DefaultTerminal) -> Result<()> {
 }
 
 impl GuiState {
+    // 添加抢票引擎的事件，领取结果前加上标记便于查看
+    pub fn add_console_event(&mut self, event: &GrabEvent) {
+        let message = match event {
+            GrabEvent::Succeeded { .. } => format!("✔ {}", event),
+            GrabEvent::Failed { .. } => format!("✘ {}", event),
+            _ => event.to_string(),
+        };
+        self.add_console_message(message);
+    }
+
     // 添加控制台消息
     pub fn add_console_message(&mut self, message: String) {
         // 获取当前时间并格式化
@@ -281,7 +403,7 @@ impl GuiState {
 
         // 添加带时间戳的新消息
         if !self.console_info.is_empty() {
-            self.console_info.push_str("\n");
+            self.console_info.push('\n');
         }
         self.console_info
             .push_str(&format!("{} {}", timestamp, message));
@@ -0,0 +1,198 @@
+// 服务器时钟校准
+//
+// 优惠券按服务器时间开抢，本地时钟可能快或慢几百毫秒甚至几秒。
+// 多次请求接口，读取响应头中的 Date，结合本地发送/接收时间估算时钟偏差和往返延迟。
+// Date 只精确到秒，每次采样只能得到偏差所在的一个区间，多次采样取交集来缩小范围。
+
+use crate::error::{Error, Result};
+use crate::utils::request::RequestContext;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::header::DATE;
+use std::path::Path;
+use std::time::Duration;
+
+// 默认采样次数
+pub const DEFAULT_SAMPLES: usize = 5;
+// 采样间隔，不取整秒以便样本落在一秒内的不同位置
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(230);
+
+// 一次采样
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub sent: DateTime<Utc>,     // 本地发送时间
+    pub received: DateTime<Utc>, // 本地收到响应时间
+    pub server: DateTime<Utc>,   // 响应头中的服务器时间（精确到秒）
+}
+
+impl Sample {
+    fn rtt(&self) -> chrono::Duration {
+        self.received - self.sent
+    }
+
+    // 该样本允许的偏差区间
+    fn bounds(&self) -> (chrono::Duration, chrono::Duration) {
+        let low = self.server - self.received;
+        let high = self.server + chrono::Duration::seconds(1) - self.sent;
+        (low, high)
+    }
+}
+
+// 校准结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockOffset {
+    pub offset: chrono::Duration, // 服务器时间 - 本地时间，正数表示服务器比本地快
+    pub rtt: chrono::Duration,    // 往返延迟（中位数）
+    pub uncertainty: chrono::Duration, // 偏差估计的误差范围
+    pub samples: usize,
+}
+
+impl Default for ClockOffset {
+    fn default() -> Self {
+        Self {
+            offset: chrono::Duration::zero(),
+            rtt: chrono::Duration::zero(),
+            uncertainty: chrono::Duration::zero(),
+            samples: 0,
+        }
+    }
+}
+
+impl ClockOffset {
+    // 根据样本估算偏差
+    pub fn estimate(samples: &[Sample]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut rtts: Vec<chrono::Duration> = samples.iter().map(Sample::rtt).collect();
+        rtts.sort();
+        let rtt = rtts[rtts.len() / 2];
+
+        let low = samples.iter().map(|s| s.bounds().0).max()?;
+        let high = samples.iter().map(|s| s.bounds().1).min()?;
+        let (offset, uncertainty) = if low <= high {
+            ((low + high) / 2, (high - low) / 2)
+        } else {
+            // 网络抖动导致区间没有交集时，退回到各样本区间中点的平均值
+            let total = samples
+                .iter()
+                .map(|s| {
+                    let (low, high) = s.bounds();
+                    (low + high) / 2
+                })
+                .fold(chrono::Duration::zero(), |acc, mid| acc + mid);
+            (
+                total / samples.len() as i32,
+                chrono::Duration::milliseconds(500) + rtt / 2,
+            )
+        };
+
+        Some(Self {
+            offset,
+            rtt,
+            uncertainty,
+            samples: samples.len(),
+        })
+    }
+
+    // 服务器时间对应的本地时间
+    pub fn to_local(&self, server_time: NaiveDateTime) -> NaiveDateTime {
+        server_time - self.offset
+    }
+
+    // 为了让请求在服务器时间 target 到达，本地应当发出请求的时间
+    pub fn fire_at(&self, target: NaiveDateTime) -> NaiveDateTime {
+        self.to_local(target) - self.rtt / 2
+    }
+}
+
+// 采样一次服务器时间
+pub async fn sample(ctx: &RequestContext) -> Result<Sample> {
+    let url = ctx.url(Path::new(""));
+    let sent = Utc::now();
+    let response = ctx.client().get(&url).send().await?;
+    let received = Utc::now();
+
+    let date = response
+        .headers()
+        .get(DATE)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| Error::InvalidResponse("响应缺少 Date 头".to_string()))?;
+    let server = DateTime::parse_from_rfc2822(date)
+        .map_err(|_| Error::InvalidResponse(date.to_string()))?
+        .with_timezone(&Utc);
+
+    Ok(Sample {
+        sent,
+        received,
+        server,
+    })
+}
+
+// 多次采样并估算时钟偏差，失败的采样会被跳过
+pub async fn calibrate(ctx: &RequestContext, count: usize) -> Result<ClockOffset> {
+    let mut samples = Vec::with_capacity(count);
+    let mut last_error = None;
+
+    for i in 0..count.max(1) {
+        if i > 0 {
+            tokio::time::sleep(SAMPLE_INTERVAL).await;
+        }
+        match sample(ctx).await {
+            Ok(sample) => samples.push(sample),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    ClockOffset::estimate(&samples).ok_or_else(|| last_error.unwrap_or(Error::Timeout))
+}
+
+#[test]
+fn test_estimate_offset() {
+    // 服务器比本地快 2.3 秒，往返延迟 40ms
+    let true_offset = chrono::Duration::milliseconds(2300);
+    let rtt = chrono::Duration::milliseconds(40);
+    let start = DateTime::parse_from_rfc3339("2026-10-18T10:00:00Z")
+        .unwrap()
+        .with_timezone(&Utc);
+
+    let samples: Vec<Sample> = (0..5)
+        .map(|i| {
+            let sent = start + chrono::Duration::milliseconds(230 * i);
+            let stamped = sent + rtt / 2 + true_offset;
+            // Date 头截断到整秒
+            let server = DateTime::from_timestamp(stamped.timestamp(), 0).unwrap();
+            Sample {
+                sent,
+                received: sent + rtt,
+                server,
+            }
+        })
+        .collect();
+
+    let clock = ClockOffset::estimate(&samples).unwrap();
+    assert!(
+        (clock.offset - true_offset).num_milliseconds().abs()
+            <= clock.uncertainty.num_milliseconds()
+    );
+    assert!(clock.uncertainty < chrono::Duration::milliseconds(300));
+    assert_eq!(clock.rtt, rtt);
+
+    let target = start.naive_utc() + chrono::Duration::seconds(60);
+    assert_eq!(
+        clock.fire_at(target),
+        target - clock.offset - chrono::Duration::milliseconds(20)
+    );
+    assert!(ClockOffset::estimate(&[]).is_none());
+}
+
+#[tokio::test]
+async fn test_calibrate() {
+    use crate::utils::mock_server::MockServer;
+
+    // 模拟服务使用本地时钟，偏差应当在一秒以内
+    let server = MockServer::start().await;
+    let clock = calibrate(&server.context(), 3).await.unwrap();
+    assert_eq!(clock.samples, 3);
+    assert!(clock.offset.num_milliseconds().abs() < 1000);
+}
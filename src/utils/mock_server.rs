@@ -0,0 +1,319 @@
+// 本地模拟的优号租接口服务，供离线测试使用
+//
+// 模拟 queryMobilePhone、coupon/center/info 与 coupon/center/receive 三个接口，
+// 每个接口的响应可以按顺序编排（成功、2040、非JSON、慢响应、token过期等），
+// 并记录收到的所有请求以便断言。
+
+use crate::utils::request::{RequestConfig, RequestContext};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+pub const QUERY_MOBILE_PHONE: &str = "userBase/queryMobilePhone";
+pub const COUPON_INFO: &str = "community/coupon/center/info";
+pub const COUPON_RECEIVE: &str = "community/coupon/center/receive";
+
+pub const MOCK_MOBILE_PHONE: &str = "13800000000";
+
+// 模拟响应
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    Json(Value),                        // 200 + JSON
+    Raw { status: u16, body: String },  // 任意状态码和响应体
+    Delay(Duration, Box<MockResponse>), // 延迟后再返回
+}
+
+impl MockResponse {
+    // 业务成功
+    pub fn success(object: Value) -> Self {
+        Self::Json(json!({
+            "object": object,
+            "responseCode": "0000",
+            "responseMsg": "成功",
+            "success": true,
+        }))
+    }
+
+    // 业务失败，带业务码
+    pub fn business(code: &str, msg: &str) -> Self {
+        Self::Json(json!({
+            "object": null,
+            "responseCode": code,
+            "responseMsg": msg,
+            "success": false,
+        }))
+    }
+
+    // 2040: 已领取或已抢完
+    pub fn already_received() -> Self {
+        Self::business("2040", "优惠券已抢完")
+    }
+
+    // 非JSON响应体
+    pub fn not_json() -> Self {
+        Self::Raw {
+            status: 200,
+            body: "<html><body>系统繁忙</body></html>".to_string(),
+        }
+    }
+
+    // token过期时服务端返回的响应
+    pub fn expired_token() -> Self {
+        Self::Raw {
+            status: 401,
+            body: r#"{"code":401,"msg":"登录已失效"}"#.to_string(),
+        }
+    }
+
+    // 慢响应
+    pub fn slow(delay: Duration, response: MockResponse) -> Self {
+        Self::Delay(delay, Box::new(response))
+    }
+
+    // 示例领券中心数据
+    pub fn coupon_center() -> Self {
+        Self::success(json!({
+            "zoneRedList": [
+                { "zoneName": "置顶专区", "redList": [{ "redPackTaskId": 57153 }] },
+                { "zoneName": "新人专区", "redList": [] },
+                {
+                    "zoneName": "每周专区",
+                    "redList": [{ "redPackTaskId": 58238 }, { "redPackTaskId": 58237 }]
+                },
+                { "zoneName": "每日专区", "redList": [{ "redPackTaskId": 59623 }] }
+            ]
+        }))
+    }
+}
+
+// 收到的请求
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_str(&self.body).ok()
+    }
+}
+
+type Routes = Arc<Mutex<HashMap<String, VecDeque<MockResponse>>>>;
+
+pub struct MockServer {
+    addr: SocketAddr,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockServer {
+    // 启动模拟服务，三个接口默认返回成功
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("无法启动模拟服务");
+        let addr = listener.local_addr().unwrap();
+
+        let routes: Routes = Arc::new(Mutex::new(HashMap::new()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let handle = {
+            let routes = routes.clone();
+            let requests = requests.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let routes = routes.clone();
+                    let requests = requests.clone();
+                    tokio::spawn(handle_connection(stream, routes, requests));
+                }
+            })
+        };
+
+        let server = Self {
+            addr,
+            routes,
+            requests,
+            handle,
+        };
+        server.respond(
+            QUERY_MOBILE_PHONE,
+            vec![MockResponse::success(
+                json!({ "mobilePhone": MOCK_MOBILE_PHONE }),
+            )],
+        );
+        server.respond(COUPON_INFO, vec![MockResponse::coupon_center()]);
+        server.respond(COUPON_RECEIVE, vec![MockResponse::success(Value::Null)]);
+        server
+    }
+
+    // 编排某个接口的响应：按顺序返回，最后一个会一直重复
+    pub fn respond(&self, path: &str, responses: Vec<MockResponse>) {
+        self.routes
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), responses.into());
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}/api/", self.addr)
+    }
+
+    // 指向模拟服务的请求上下文
+    pub fn context(&self) -> RequestContext {
+        RequestContext::new(RequestConfig::default().with_base_url(self.base_url())).unwrap()
+    }
+
+    // 收到的全部请求
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // 某个接口收到的请求
+    pub fn requests_to(&self, path: &str) -> Vec<RecordedRequest> {
+        self.requests()
+            .into_iter()
+            .filter(|r| r.path == path)
+            .collect()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_connection(
+    mut stream: TcpStream,
+    routes: Routes,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+) {
+    let mut buffer = Vec::new();
+    // 同一连接上可能有多个请求（keep-alive）
+    while let Some(request) = read_request(&mut stream, &mut buffer).await {
+        let response = {
+            let mut routes = routes.lock().unwrap();
+            match routes.get_mut(&request.path) {
+                Some(queue) if queue.len() > 1 => queue.pop_front(),
+                Some(queue) => queue.front().cloned(),
+                None => None,
+            }
+        };
+        requests.lock().unwrap().push(request);
+
+        let response = response.unwrap_or(MockResponse::Raw {
+            status: 404,
+            body: "not found".to_string(),
+        });
+        if write_response(&mut stream, response).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn read_request(stream: &mut TcpStream, buffer: &mut Vec<u8>) -> Option<RecordedRequest> {
+    // 读取请求头
+    let header_end = loop {
+        if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+        .collect();
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+
+    // 读取请求体
+    while buffer.len() < header_end + content_length {
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+    }
+    let body =
+        String::from_utf8_lossy(&buffer[header_end..header_end + content_length]).to_string();
+    buffer.drain(..header_end + content_length);
+
+    let path = target
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .trim_start_matches("api/")
+        .to_string();
+
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+async fn write_response(stream: &mut TcpStream, response: MockResponse) -> std::io::Result<()> {
+    let mut response = response;
+    while let MockResponse::Delay(delay, inner) = response {
+        tokio::time::sleep(delay).await;
+        response = *inner;
+    }
+
+    let (status, content_type, body) = match response {
+        MockResponse::Json(value) => (200, "application/json", value.to_string()),
+        MockResponse::Raw { status, body } => (status, "text/html; charset=utf-8", body),
+        MockResponse::Delay(..) => unreachable!(),
+    };
+    let reason = match status {
+        200 => "OK",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        _ => "Unknown",
+    };
+    let date = chrono::Utc::now().format("%a, %d %b %Y %H:%M:%S GMT");
+
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nDate: {}\r\nConnection: keep-alive\r\n\r\n",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        date
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.flush().await
+}
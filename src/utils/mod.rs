@@ -1,4 +1,10 @@
 // use request::request;
 
 pub mod request;
-pub mod proxy;
\ No newline at end of file
+pub mod proxy;
+pub mod clock;
+pub mod scheduler;
+pub mod vault;
+pub mod persist;
+#[cfg(test)]
+pub mod mock_server;
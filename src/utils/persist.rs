@@ -0,0 +1,182 @@
+// 配置文件的写入
+//
+// 先写到同目录下的临时文件再重命名覆盖，崩溃或断电时不会留下写了一半的文件。
+// 覆盖前把原文件保存为 .bak.1 ~ .bak.3，数字越大越旧。
+// 写入期间持有同名 .lock 文件的排他锁，界面和后台模式同时运行时不会同时写同一个文件。
+use crate::error::Result;
+use fs4::fs_std::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// 保留的备份数
+pub const BACKUPS: usize = 3;
+
+// 配置文件的排他锁，离开作用域时释放
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    // 等待其他进程释放锁
+    pub fn acquire(path: &Path) -> Result<Self> {
+        let file = open_lock_file(path)?;
+        file.lock_exclusive()?;
+        Ok(Self { file })
+    }
+
+    // 不等待，其他进程持有锁时返回 None
+    pub fn try_acquire(path: &Path) -> Result<Option<Self>> {
+        let file = open_lock_file(path)?;
+        Ok(FileExt::try_lock_exclusive(&file)?.then_some(Self { file }))
+    }
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    Ok(OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(suffixed(path, "lock"))?)
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+pub fn backup_path(path: &Path, n: usize) -> PathBuf {
+    suffixed(path, &format!("bak.{}", n))
+}
+
+// 调用方已经持有锁，用于读取、修改、写回期间一直持有锁的场景
+pub fn write_locked(_lock: &FileLock, path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+
+    let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+    temp.write_all(content.as_ref())?;
+    temp.as_file().sync_all()?;
+
+    if path.exists() {
+        rotate_backups(path)?;
+    }
+    temp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+// 写入只有当前用户能读写的文件，例如导出的账号，已有的文件同样改为只有当前用户能读写
+pub fn write_private(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    let mut file = options.open(path)?;
+    file.write_all(content.as_ref())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+// 删除所有备份，例如文件改为加密保存后不再保留明文的旧版本
+pub fn remove_backups(path: &Path) -> Result<()> {
+    for n in 1..=BACKUPS {
+        let backup = backup_path(path, n);
+        if backup.exists() {
+            fs::remove_file(backup)?;
+        }
+    }
+    Ok(())
+}
+
+fn rotate_backups(path: &Path) -> Result<()> {
+    for n in (1..BACKUPS).rev() {
+        let from = backup_path(path, n);
+        if from.exists() {
+            fs::rename(from, backup_path(path, n + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+#[test]
+fn test_write() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("UTermux").join("AppGame.json");
+    let write = |path: &Path, content: String| {
+        let lock = FileLock::acquire(path)?;
+        write_locked(&lock, path, content)
+    };
+
+    for i in 0..5 {
+        write(&path, format!("v{}", i)).unwrap();
+    }
+    assert_eq!(fs::read_to_string(&path).unwrap(), "v4");
+    // 只保留最近的几份旧版本
+    assert_eq!(fs::read_to_string(backup_path(&path, 1)).unwrap(), "v3");
+    assert_eq!(fs::read_to_string(backup_path(&path, 3)).unwrap(), "v1");
+    assert!(!backup_path(&path, 4).exists());
+
+    // 多个线程同时写入时文件内容总是完整的
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let path = path.clone();
+            std::thread::spawn(move || write(&path, format!("thread-{}", i).repeat(1000)))
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap().unwrap();
+    }
+    let content = fs::read_to_string(&path).unwrap();
+    assert!((0..8).any(|i| content == format!("thread-{}", i).repeat(1000)));
+}
+
+#[test]
+fn test_try_acquire() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("runner");
+    let lock = FileLock::try_acquire(&path).unwrap();
+    assert!(lock.is_some());
+    // 持有锁期间再次获取失败，释放后可以获取
+    assert!(FileLock::try_acquire(&path).unwrap().is_none());
+    drop(lock);
+    assert!(FileLock::try_acquire(&path).unwrap().is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_write_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("accounts.json");
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+    write_private(&path, "new").unwrap();
+    assert_eq!(mode(&path), 0o600);
+    // 已有的文件同样改为只有当前用户能读写
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+    write_private(&path, "again").unwrap();
+    assert_eq!(mode(&path), 0o600);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "again");
+}
@@ -27,6 +27,7 @@ impl ProxyConfig {
 
 
 #[tokio::test]
+#[ignore = "需要访问外部代理列表"]
 async fn test_get_proxy() {
     let proxy_config = ProxyConfig::new(vec![]);
     let proxy_list = proxy_config.get_proxy().await;
@@ -1,6 +1,8 @@
-use reqwest::Client;
+use crate::error::{Error, Result};
+use reqwest::{Client, StatusCode};
 use serde::Serialize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Serialize, Clone)]
 pub struct Data {
@@ -14,18 +16,35 @@ pub struct LoginData {
     pub id: String,
 }
 
+// 账号的请求头，创建时整理好token，之后每次请求直接使用
 #[derive(Debug, Clone)]
 pub struct Headers {
     pub auth_token: String,
     pub user_agent: Option<String>,
+    pub extra: Vec<(String, String)>, // 其他请求头，例如设备指纹
 }
 
 impl Headers {
     pub fn new(auth_token: impl Into<String>, user_agent: impl Into<String>) -> Self {
-        Self {
+        let mut headers = Self::token(auth_token);
+        headers.user_agent = Some(user_agent.into());
+        headers
+    }
+
+    // 只带token的请求头
+    pub fn token(auth_token: impl Into<String>) -> Self {
+        let mut headers = Self {
             auth_token: auth_token.into(),
-            user_agent: Some(user_agent.into()),
-        }
+            user_agent: None,
+            extra: Vec::new(),
+        };
+        headers.clean_auth_token();
+        headers
+    }
+
+    pub fn with_extra(mut self, extra: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.extra.extend(extra);
+        self
     }
 
     // 只提取AuthToken后面的值，忽略MobilePhone部分
@@ -41,43 +60,185 @@ impl Headers {
     }
 }
 
-// 请求函数
-pub async fn request<T>(
-    path: PathBuf,
-    data: Option<T>,
-    mut headers: Option<Headers>,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>>
-where
-    T: Serialize,
-{
-    let client = Client::new();
-    let url = format!("https://mapi.uhaozu.com/api/{}", path.display());
-
-    let mut request = client.post(&url);
-
-    // 添加请求头
-    if let Some(ref mut headers) = headers {
-        headers.clean_auth_token();
-        request = request
-            .header("authToken", &headers.auth_token)
-            .header("user-agent", headers.user_agent.as_deref().unwrap_or(""));
+// 默认接口地址
+pub const DEFAULT_BASE_URL: &str = "https://mapi.uhaozu.com/api/";
+// 覆盖接口地址的环境变量，用于指向本地模拟服务或预发布环境
+pub const BASE_URL_ENV: &str = "UTERMUX_API_BASE_URL";
+
+// HTTP客户端配置
+#[derive(Debug, Clone)]
+pub struct RequestConfig {
+    pub base_url: String,              // 接口根地址
+    pub connect_timeout: Duration,     // 建立连接超时
+    pub read_timeout: Duration,        // 读取响应超时
+    pub pool_idle_timeout: Duration,   // 空闲连接保留时间
+    pub pool_max_idle_per_host: usize, // 每个主机保留的最大空闲连接数
+    pub tcp_keepalive: Duration,       // TCP keep-alive 间隔
+}
+
+impl Default for RequestConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            connect_timeout: Duration::from_secs(3),
+            read_timeout: Duration::from_secs(5),
+            pool_idle_timeout: Duration::from_secs(90),
+            pool_max_idle_per_host: 16,
+            tcp_keepalive: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RequestConfig {
+    // 读取环境变量中的接口地址，未设置时使用默认地址
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        if let Ok(base_url) = std::env::var(BASE_URL_ENV) {
+            if !base_url.trim().is_empty() {
+                config.base_url = base_url.trim().to_string();
+            }
+        }
+        config
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+}
+
+// 请求上下文，持有长期存活的HTTP客户端（连接池、keep-alive、HTTP/2）
+// Client 内部是引用计数的，clone 后仍共享同一个连接池
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    client: Client,
+    config: RequestConfig,
+}
+
+impl RequestContext {
+    pub fn new(config: RequestConfig) -> std::result::Result<Self, reqwest::Error> {
+        let client = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .read_timeout(config.read_timeout)
+            .pool_idle_timeout(config.pool_idle_timeout)
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .tcp_keepalive(config.tcp_keepalive)
+            .tcp_nodelay(true)
+            .build()?;
+        Ok(Self { client, config })
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    pub fn config(&self) -> &RequestConfig {
+        &self.config
     }
-    // 只在有data时添加请求体
-    if let Some(data) = data {
-        request = request.json(&data);
+
+    // 拼接接口完整地址
+    pub fn url(&self, path: &Path) -> String {
+        let path = path.to_string_lossy().replace('\\', "/");
+        format!(
+            "{}/{}",
+            self.config.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
     }
 
-    let response = request.send().await?;
-    Ok(response.text().await?)
+    // 请求函数
+    pub async fn request<T>(
+        &self,
+        path: PathBuf,
+        data: Option<T>,
+        headers: Option<&Headers>,
+    ) -> Result<String>
+    where
+        T: Serialize,
+    {
+        let url = self.url(&path);
+
+        let mut request = self.client.post(&url);
+
+        // 添加请求头
+        if let Some(headers) = headers {
+            request = request
+                .header("authToken", &headers.auth_token)
+                .header("user-agent", headers.user_agent.as_deref().unwrap_or(""));
+            for (name, value) in &headers.extra {
+                request = request.header(name, value);
+            }
+        }
+        // 只在有data时添加请求体
+        if let Some(data) = data {
+            request = request.json(&data);
+        }
+
+        let response = request.send().await?;
+        match response.status() {
+            StatusCode::TOO_MANY_REQUESTS => Err(Error::RateLimited(
+                response.text().await.unwrap_or_default(),
+            )),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(Error::TokenExpired),
+            _ => Ok(response.text().await?),
+        }
+    }
+}
+
+impl Default for RequestContext {
+    fn default() -> Self {
+        Self::new(RequestConfig::default()).expect("无法创建HTTP客户端")
+    }
 }
 
 #[tokio::test]
 async fn test_request() {
-    let reponse = Client::new()
-        .get("https://mapi.uhaozu.com/api/userBase/queryMobilePhone")
-        .header("authToken", "d8AZUpWrOsfV1GUfqhPS4EQ08BfnRuJ2xIRU0hrPRCD9l32AHpr5QgqtPysy5y_cLJ5vuDm34Cwj2fltIbFO6HFfVzG85e551gKisodEf16uUcScTsPNhF89U7XCb7Tp6UWvv2SAq22V2NfQW17DZUC8MNXD-zmIXV2AhZaBBN3NcGdSLOj-ncVZO0YWyeGby9-qArsxP7cfQtg4OSw8")
-        .header("user-agent", "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
-        .send()
-        .await;
-    println!("{}", reponse.unwrap().text().await.unwrap());
+    use crate::utils::mock_server::{MockServer, QUERY_MOBILE_PHONE};
+
+    let server = MockServer::start().await;
+    let ctx = server.context();
+    let headers = Headers::new(
+        "AuthToken: test-token\nMobilePhone: 13800000000",
+        "Mozilla/5.0 (Windows NT 10.0; Win64; x64)",
+    )
+    .with_extra([("deviceId".to_string(), "device-1".to_string())]);
+    let response = ctx
+        .request(
+            PathBuf::from(QUERY_MOBILE_PHONE),
+            Some(LoginData {
+                id: "14".to_string(),
+            }),
+            Some(&headers),
+        )
+        .await
+        .unwrap();
+    assert!(response.contains("13800000000"));
+
+    let requests = server.requests_to(QUERY_MOBILE_PHONE);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, "POST");
+    assert_eq!(requests[0].header("authToken"), Some("test-token"));
+    assert_eq!(requests[0].header("deviceId"), Some("device-1"));
+    assert_eq!(
+        requests[0].header("user-agent"),
+        Some("Mozilla/5.0 (Windows NT 10.0; Win64; x64)")
+    );
+    assert_eq!(requests[0].json().unwrap()["id"], "14");
+}
+
+#[test]
+fn test_request_url() {
+    let ctx =
+        RequestContext::new(RequestConfig::default().with_base_url("http://127.0.0.1:8080/api"))
+            .unwrap();
+    assert_eq!(
+        ctx.url(Path::new("userBase/queryMobilePhone")),
+        "http://127.0.0.1:8080/api/userBase/queryMobilePhone"
+    );
+
+    let ctx = RequestContext::default();
+    assert_eq!(
+        ctx.url(Path::new("community/coupon/center/info")),
+        "https://mapi.uhaozu.com/api/community/coupon/center/info"
+    );
 }
@@ -0,0 +1,95 @@
+// 高精度定时
+//
+// 先用普通的 sleep 粗略等待，每秒按本地时钟重新对齐（防止系统休眠导致单调时钟漂移），
+// 最后几毫秒改为自旋等待，使请求尽量准时发出。
+
+use chrono::{Local, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+// 粗略等待时每次最多睡眠的时长
+const COARSE_STEP: Duration = Duration::from_secs(1);
+// 最后改为自旋等待的时长，需要大于定时器的精度
+const SPIN_WINDOW: Duration = Duration::from_millis(3);
+
+// 一次定时抢票的倒计时信息，供状态栏显示
+#[derive(Debug, Clone, PartialEq)]
+pub struct Countdown {
+    pub target: NaiveDateTime,  // 服务器时间的开抢时刻
+    pub fire_at: NaiveDateTime, // 校准并提前后，本地发出请求的时刻
+    pub lead: Duration,         // 提前发出的时长
+}
+
+// 各定时任务的倒计时，按任务编号索引
+pub type Countdowns = BTreeMap<u64, Countdown>;
+
+impl Countdown {
+    // 距离发出请求的剩余时间
+    pub fn remaining(&self) -> Duration {
+        until(self.fire_at)
+    }
+}
+
+// 距离本地时间 fire_at 的时长，已过去则为0
+fn until(fire_at: NaiveDateTime) -> Duration {
+    (fire_at - Local::now().naive_local())
+        .to_std()
+        .unwrap_or_default()
+}
+
+// 粗略等待阶段下一次睡眠的时长，剩余时间不多时返回 None，改为精确等待
+fn coarse_sleep(remaining: Duration) -> Option<Duration> {
+    (remaining > COARSE_STEP + SPIN_WINDOW).then(|| COARSE_STEP.min(remaining - COARSE_STEP))
+}
+
+// 精确等待阶段自旋前睡眠的时长
+fn fine_sleep(remaining: Duration) -> Duration {
+    remaining.saturating_sub(SPIN_WINDOW)
+}
+
+// 等待到本地时间 fire_at，返回实际晚了多少
+pub async fn wait_until(fire_at: NaiveDateTime) -> Duration {
+    // 粗略等待
+    while let Some(sleep) = coarse_sleep(until(fire_at)) {
+        tokio::time::sleep(sleep).await;
+    }
+
+    // 换算成单调时钟的截止时刻，精确等待
+    let deadline = Instant::now() + until(fire_at);
+    let fine = fine_sleep(deadline.saturating_duration_since(Instant::now()));
+    if !fine.is_zero() {
+        tokio::time::sleep(fine).await;
+    }
+    while Instant::now() < deadline {
+        std::hint::spin_loop();
+    }
+
+    Instant::now().duration_since(deadline)
+}
+
+#[test]
+fn test_sleep_steps() {
+    let ms = Duration::from_millis;
+    // 每次最多睡眠一秒，最后一秒多留给精确等待
+    assert_eq!(coarse_sleep(ms(5000)), Some(COARSE_STEP));
+    assert_eq!(coarse_sleep(ms(1500)), Some(ms(500)));
+    assert_eq!(coarse_sleep(COARSE_STEP + SPIN_WINDOW), None);
+    assert_eq!(coarse_sleep(Duration::ZERO), None);
+    // 自旋前睡到只剩自旋窗口，已经过去时不睡眠
+    assert_eq!(fine_sleep(ms(800)), ms(800) - SPIN_WINDOW);
+    assert_eq!(fine_sleep(ms(1)), Duration::ZERO);
+}
+
+#[tokio::test]
+#[ignore = "依赖真实的睡眠精度，机器繁忙时可能失败"]
+async fn test_wait_until() {
+    let fire_at = Local::now().naive_local() + chrono::Duration::milliseconds(1300);
+    let late = wait_until(fire_at).await;
+    assert!(Local::now().naive_local() >= fire_at);
+    assert!(late < Duration::from_millis(20), "晚了 {:?}", late);
+
+    // 已经过去的时间立即返回
+    let start = Instant::now();
+    wait_until(fire_at).await;
+    assert!(start.elapsed() < Duration::from_millis(20));
+}
@@ -0,0 +1,209 @@
+// 账号文件加密
+//
+// 口令经 Argon2id 派生出密钥，用 ChaCha20-Poly1305 加密，盐、随机数和派生参数随密文一起保存，
+// 解密时按保存的参数派生密钥，以后调整默认参数不影响已加密的文件。密文被篡改或口令错误时解密失败。解锁后只在内存中保存派生出的密钥，不保存口令，
+// 密钥在释放时清零。
+
+use crate::error::{Error, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use zeroize::Zeroizing;
+
+// 加密格式的版本
+pub const VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+// 派生密钥使用的 Argon2 参数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Kdf {
+    pub algorithm: String,
+    pub version: u32,
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Argon2id.as_str().to_string(),
+            version: Version::V0x13 as u32,
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl Kdf {
+    fn argon2(&self) -> Result<Argon2<'static>> {
+        let unsupported = || {
+            Error::Config(format!(
+                "不支持的密钥派生参数 {} v{}",
+                self.algorithm, self.version
+            ))
+        };
+        let algorithm = Algorithm::new(&self.algorithm).map_err(|_| unsupported())?;
+        let version = Version::try_from(self.version).map_err(|_| unsupported())?;
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LEN))
+            .map_err(|e| Error::Config(format!("密钥派生参数无效: {}", e)))?;
+        Ok(Argon2::new(algorithm, version, params))
+    }
+}
+
+// 加密后的内容，字段均为 base64
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sealed {
+    pub version: u32,
+    pub kdf: Kdf,
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+// 由口令、盐和派生参数得到的密钥
+#[derive(Clone)]
+pub struct Vault {
+    kdf: Kdf,
+    salt: Vec<u8>,
+    key: Zeroizing<[u8; KEY_LEN]>,
+}
+
+// 当前会话解锁的密钥
+static UNLOCKED: Lazy<Mutex<Option<Vault>>> = Lazy::new(|| Mutex::new(None));
+
+impl Vault {
+    // 使用新的随机盐和默认参数
+    pub fn new(passphrase: &str) -> Result<Self> {
+        let mut salt = vec![0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(passphrase, Kdf::default(), salt)
+    }
+
+    pub fn derive(passphrase: &str, kdf: Kdf, salt: Vec<u8>) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(Error::Config("口令不能为空".to_string()));
+        }
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        kdf.argon2()?
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut *key)
+            .map_err(|e| Error::Config(format!("派生密钥失败: {}", e)))?;
+        Ok(Self { kdf, salt, key })
+    }
+
+    // 使用密文中的盐和派生参数
+    pub fn for_sealed(passphrase: &str, sealed: &Sealed) -> Result<Self> {
+        let salt = STANDARD.decode(&sealed.salt).map_err(|_| Error::Decrypt)?;
+        Self::derive(passphrase, sealed.kdf.clone(), salt)
+    }
+
+    // 每次加密使用新的随机数
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Sealed> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher()
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| Error::Config("加密失败".to_string()))?;
+        Ok(Sealed {
+            version: VERSION,
+            kdf: self.kdf.clone(),
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&*self.key))
+    }
+
+    // 盐或派生参数不同说明文件被其他进程重新加密过，密钥已经不对应，需要重新输入口令解锁
+    pub fn open(&self, sealed: &Sealed) -> Result<Vec<u8>> {
+        if sealed.version != VERSION {
+            return Err(Error::Config(format!(
+                "不支持的加密格式 v{}",
+                sealed.version
+            )));
+        }
+        let decode = |value: &str| STANDARD.decode(value).map_err(|_| Error::Decrypt);
+        if decode(&sealed.salt)? != self.salt || sealed.kdf != self.kdf {
+            return Err(Error::Locked);
+        }
+        let nonce = decode(&sealed.nonce)?;
+        let ciphertext = decode(&sealed.ciphertext)?;
+        if nonce.len() != 12 {
+            return Err(Error::Decrypt);
+        }
+
+        self.cipher()
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| Error::Decrypt)
+    }
+}
+
+// 当前会话解锁的密钥，未解锁时为空
+pub fn unlocked() -> Option<Vault> {
+    UNLOCKED.lock().unwrap().clone()
+}
+
+pub fn set_unlocked(vault: Option<Vault>) {
+    *UNLOCKED.lock().unwrap() = vault;
+}
+
+#[test]
+fn test_vault() {
+    let vault = Vault::new("correct horse").unwrap();
+    let sealed = vault.seal(b"{\"records\":[]}").unwrap();
+    assert_eq!(vault.open(&sealed).unwrap(), b"{\"records\":[]}");
+
+    // 不保存口令，盐不同时需要用口令重新派生
+    let other = Vault::new("correct horse").unwrap();
+    assert!(matches!(other.open(&sealed), Err(Error::Locked)));
+    let reopened = Vault::for_sealed("correct horse", &sealed).unwrap();
+    assert_eq!(reopened.open(&sealed).unwrap(), b"{\"records\":[]}");
+
+    // 口令错误或密文被篡改时解密失败
+    let wrong = Vault::for_sealed("wrong", &sealed).unwrap();
+    assert!(matches!(wrong.open(&sealed), Err(Error::Decrypt)));
+    let mut tampered = sealed.clone();
+    tampered.ciphertext = STANDARD.encode(b"tampered ciphertext bytes");
+    assert!(matches!(vault.open(&tampered), Err(Error::Decrypt)));
+
+    assert!(Vault::new("").is_err());
+}
+
+#[test]
+fn test_vault_kdf() {
+    // 解密时使用密文中保存的参数，而不是当前的默认参数
+    let kdf = Kdf {
+        m_cost: 8 * 1024,
+        t_cost: 1,
+        ..Kdf::default()
+    };
+    let vault = Vault::derive("correct horse", kdf.clone(), vec![7u8; SALT_LEN]).unwrap();
+    let sealed = vault.seal(b"secret").unwrap();
+    assert_eq!(sealed.kdf, kdf);
+    let reopened = Vault::for_sealed("correct horse", &sealed).unwrap();
+    assert_eq!(reopened.open(&sealed).unwrap(), b"secret");
+
+    // 参数不同时派生出的密钥不同
+    let mut changed = sealed.clone();
+    changed.kdf = Kdf::default();
+    assert!(matches!(vault.open(&changed), Err(Error::Locked)));
+    let wrong = Vault::for_sealed("correct horse", &changed).unwrap();
+    assert!(matches!(wrong.open(&changed), Err(Error::Decrypt)));
+
+    let unsupported = Kdf {
+        algorithm: "scrypt".to_string(),
+        ..Kdf::default()
+    };
+    assert!(Vault::derive("correct horse", unsupported, vec![7u8; SALT_LEN]).is_err());
+}